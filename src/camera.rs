@@ -0,0 +1,162 @@
+use super::*;
+use gui::c;
+use rltk::prelude::{BLACK, DARK_MAGENTA};
+
+// マップを表示する画面の範囲。下の7行はUIが使う
+pub const VIEW_WIDTH: i32 = 80;
+pub const VIEW_HEIGHT: i32 = 43;
+
+// 画面左上に表示するマップ上の座標。プレイヤーを中心にし、マップの端では止める
+fn screen_origin(map: &Map, player_pos: &Point) -> Point {
+    let max_x = i32::max(0, map.width as i32 - VIEW_WIDTH);
+    let max_y = i32::max(0, map.height as i32 - VIEW_HEIGHT);
+    Point::new(
+        i32::min(max_x, i32::max(0, player_pos.x - VIEW_WIDTH / 2)),
+        i32::min(max_y, i32::max(0, player_pos.y - VIEW_HEIGHT / 2)),
+    )
+}
+
+fn origin(world: &World) -> Point {
+    let map = world.resources.get::<Map>().unwrap();
+    let player_pos = world.resources.get::<Point>().unwrap();
+    screen_origin(&map, &player_pos)
+}
+
+pub fn world_to_screen(world: &World, pos: Point) -> Option<Point> {
+    let origin = origin(world);
+    let screen = Point::new(pos.x - origin.x, pos.y - origin.y);
+    if screen.x < 0 || screen.x >= VIEW_WIDTH || screen.y < 0 || screen.y >= VIEW_HEIGHT {
+        None
+    } else {
+        Some(screen)
+    }
+}
+
+// マップの表示範囲外ならNoneを返す
+pub fn screen_to_world(world: &World, screen: (i32, i32)) -> Option<Point> {
+    let map = world.resources.get::<Map>().unwrap();
+    let origin = origin(world);
+    let pos = Point::new(screen.0 + origin.x, screen.1 + origin.y);
+    if screen.0 < 0
+        || screen.0 >= VIEW_WIDTH
+        || screen.1 < 0
+        || screen.1 >= VIEW_HEIGHT
+        || pos.x >= map.width as i32
+        || pos.y >= map.height as i32
+    {
+        None
+    } else {
+        Some(pos)
+    }
+}
+
+pub fn render_camera(world: &World, ctx: &mut Rltk) {
+    let map = world.resources.get::<Map>().unwrap();
+    let origin = origin(world);
+
+    for screen_y in 0..VIEW_HEIGHT {
+        for screen_x in 0..VIEW_WIDTH {
+            let x = screen_x + origin.x;
+            let y = screen_y + origin.y;
+            if x >= map.width as i32 || y >= map.height as i32 {
+                continue;
+            }
+            let idx = map.xy_idx(x, y);
+            if map.revealed_tiles[idx] {
+                let (glyph, fg) = tile_glyph(&map, idx);
+                ctx.set(screen_x, screen_y, fg, c(BLACK), glyph);
+            }
+        }
+    }
+
+    let mut data = <(Read<Position>, Read<Renderable>)>::query()
+        .filter(!component::<Hidden>())
+        .iter_immutable(world)
+        .collect::<Vec<_>>();
+    data.sort_by(|a, b| b.1.render_order.cmp(&a.1.render_order));
+    for (pos, render) in data.iter() {
+        let idx = map.xy_idx(pos.x, pos.y);
+        if !map.visible_tiles[idx] {
+            continue;
+        }
+        if let Some(screen) = world_to_screen(world, Point::new(pos.x, pos.y)) {
+            ctx.set(screen.x, screen.y, render.fg, render.bg, render.glyph)
+        }
+    }
+
+    render_detected_monsters(world, &map, ctx);
+}
+
+// 感知の効果が続いている間は、見えていないモンスターも背景に色をつけて表示する
+fn render_detected_monsters(world: &World, map: &Map, ctx: &mut Rltk) {
+    let player_entity = *world.resources.get::<Entity>().unwrap();
+    if world
+        .get_component::<DetectsMonsters>(player_entity)
+        .is_none()
+    {
+        return;
+    }
+
+    for (pos, render) in <(Read<Position>, Read<Renderable>)>::query()
+        .filter(tag::<Monster>())
+        .iter_immutable(world)
+    {
+        if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+            continue;
+        }
+        if let Some(screen) = world_to_screen(world, Point::new(pos.x, pos.y)) {
+            ctx.set(screen.x, screen.y, render.fg, c(DARK_MAGENTA), render.glyph)
+        }
+    }
+}
+
+fn tile_glyph(map: &Map, idx: usize) -> (u8, RGB) {
+    let glyph;
+    let mut fg;
+    match map.tiles[idx] {
+        TileType::Floor => {
+            glyph = rltk::to_cp437('.');
+            fg = RGB::from_f32(0.0, 0.5, 0.5);
+        }
+        TileType::Wall => {
+            glyph = rltk::to_cp437('#');
+            fg = RGB::from_f32(0., 1.0, 0.);
+        }
+        TileType::DownStairs => {
+            glyph = rltk::to_cp437('>');
+            fg = RGB::from_f32(0.0, 1.0, 1.0);
+        }
+        TileType::UpStairs => {
+            glyph = rltk::to_cp437('<');
+            fg = RGB::from_f32(0.0, 1.0, 1.0);
+        }
+        TileType::ShallowWater => {
+            glyph = rltk::to_cp437('~');
+            fg = RGB::from_f32(0.3, 0.6, 1.0);
+        }
+        TileType::DeepWater => {
+            glyph = rltk::to_cp437('~');
+            fg = RGB::from_f32(0.0, 0.1, 0.8);
+        }
+        TileType::Lava => {
+            glyph = rltk::to_cp437('~');
+            fg = RGB::from_f32(1.0, 0.3, 0.0);
+        }
+        TileType::Bridge => {
+            glyph = rltk::to_cp437('=');
+            fg = RGB::from_f32(0.6, 0.4, 0.2);
+        }
+        TileType::Grass => {
+            glyph = rltk::to_cp437('"');
+            fg = RGB::from_f32(0.2, 0.7, 0.2);
+        }
+        TileType::Gravel => {
+            glyph = rltk::to_cp437(';');
+            fg = RGB::from_f32(0.5, 0.5, 0.5);
+        }
+    }
+    if !map.visible_tiles[idx] {
+        fg = fg.to_greyscale()
+    }
+    (glyph, fg)
+}
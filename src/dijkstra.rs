@@ -0,0 +1,136 @@
+use super::map::Map;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+pub const UNREACHABLE: f32 = f32::MAX;
+
+const NEIGHBOURS: [(i32, i32, f32); 8] = [
+    (-1, 0, 1.0),
+    (1, 0, 1.0),
+    (0, -1, 1.0),
+    (0, 1, 1.0),
+    (-1, -1, 1.45),
+    (1, -1, 1.45),
+    (-1, 1, 1.45),
+    (1, 1, 1.45),
+];
+
+// 複数のゴールまでの歩く距離をタイルごとに持つ。
+// 歩けて危険のないタイルだけを地形のコスト込みでたどり、エンティティによる通行止めは一歩ずつ進むときに考える
+#[derive(Clone, Default)]
+pub struct DijkstraMap {
+    width: usize,
+    height: usize,
+    distances: Vec<f32>,
+}
+
+#[derive(PartialEq)]
+struct Node {
+    idx: usize,
+    distance: f32,
+}
+
+impl Eq for Node {}
+
+// BinaryHeapは大きい順に取り出すので、距離の比較を逆にする
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .partial_cmp(&self.distance)
+            .unwrap()
+            .then_with(|| self.idx.cmp(&other.idx))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl DijkstraMap {
+    pub fn new(map: &Map, goals: &[usize]) -> Self {
+        let mut dijkstra_map = Self::default();
+        dijkstra_map.rebuild(map, goals);
+        dijkstra_map
+    }
+
+    pub fn rebuild(&mut self, map: &Map, goals: &[usize]) {
+        self.width = map.width;
+        self.height = map.height;
+        self.distances = vec![UNREACHABLE; map.tiles.len()];
+
+        let mut open = BinaryHeap::new();
+        for goal in goals.iter() {
+            self.distances[*goal] = 0.0;
+            open.push(Node {
+                idx: *goal,
+                distance: 0.0,
+            });
+        }
+
+        while let Some(Node { idx, distance }) = open.pop() {
+            if distance > self.distances[idx] {
+                continue;
+            }
+            for (next, cost) in self.exits(map, idx) {
+                let next_distance = distance + cost;
+                if next_distance < self.distances[next] {
+                    self.distances[next] = next_distance;
+                    open.push(Node {
+                        idx: next,
+                        distance: next_distance,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn distances(&self) -> &[f32] {
+        &self.distances
+    }
+
+    // ゴールに一番近づける、ふさがれていない隣のタイル
+    pub fn step_toward(&self, map: &Map, idx: usize) -> Option<usize> {
+        self.exits(map, idx)
+            .into_iter()
+            .map(|(next, _)| next)
+            .filter(|next| !map.blocked[*next] && self.distances[*next] < self.distances[idx])
+            .min_by(|a, b| self.distances[*a].partial_cmp(&self.distances[*b]).unwrap())
+    }
+
+    // ゴールから一番離れられる、ふさがれていない隣のタイル
+    pub fn step_away(&self, map: &Map, idx: usize) -> Option<usize> {
+        self.exits(map, idx)
+            .into_iter()
+            .map(|(next, _)| next)
+            .filter(|next| {
+                !map.blocked[*next]
+                    && self.distances[*next] != UNREACHABLE
+                    && self.distances[*next] > self.distances[idx]
+            })
+            .max_by(|a, b| self.distances[*a].partial_cmp(&self.distances[*b]).unwrap())
+    }
+
+    fn exits(&self, map: &Map, idx: usize) -> Vec<(usize, f32)> {
+        let x = (idx % self.width) as i32;
+        let y = (idx / self.width) as i32;
+        NEIGHBOURS
+            .iter()
+            .filter_map(|(dx, dy, cost)| {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 {
+                    return None;
+                }
+                let next = map.xy_idx(nx, ny);
+                let tile = map.tiles[next];
+                if !tile.is_walkable() || tile.is_hazardous() {
+                    None
+                } else {
+                    Some((next, cost * tile.movement_cost()))
+                }
+            })
+            .collect()
+    }
+}
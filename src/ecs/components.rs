@@ -1,14 +1,18 @@
-use super::entity_holder::EntityHolder;
+use super::entity_holder::{EntityHolder, MissingEntity};
 use legion::entity;
 use rltk::RGB;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub trait Component {
-    fn restore_entity(&mut self, entity_dic: &HashMap<String, entity::Entity>) {
+    fn restore_entity(
+        &mut self,
+        entity_dic: &HashMap<String, entity::Entity>,
+    ) -> Result<(), MissingEntity> {
         for member in self.entity_members() {
-            member.restore_entity(entity_dic);
+            member.restore_entity(entity_dic)?;
         }
+        Ok(())
     }
 
     fn store_entity_id(&mut self) {
@@ -48,6 +52,17 @@ impl BlocksTile {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlocksVisibility;
+
+impl Component for BlocksVisibility {}
+
+impl BlocksVisibility {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
 // C
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CombatStats {
@@ -97,6 +112,33 @@ impl DefenseBonus {
 
 impl Component for DefenseBonus {}
 
+// 範囲外のモンスターの位置がturnsの間わかる
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DetectsMonsters {
+    pub turns: i32,
+}
+
+impl DetectsMonsters {
+    pub fn new(turns: i32) -> Self {
+        Self { turns }
+    }
+}
+
+impl Component for DetectsMonsters {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Door {
+    pub open: bool,
+}
+
+impl Door {
+    pub fn new(open: bool) -> Self {
+        Self { open }
+    }
+}
+
+impl Component for Door {}
+
 // E
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum EquipmentSlot {
@@ -104,6 +146,28 @@ pub enum EquipmentSlot {
     Shield,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntityMoved;
+
+impl EntityMoved {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Component for EntityMoved {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntryTrigger;
+
+impl EntryTrigger {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Component for EntryTrigger {}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Equippable {
     pub slot: EquipmentSlot,
@@ -141,6 +205,17 @@ impl Component for Equipped {
 // F
 // G
 // H
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hidden;
+
+impl Hidden {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Component for Hidden {}
+
 // I
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct InBackpack {
@@ -178,6 +253,17 @@ impl Component for InflictsDamage {}
 // K
 // L
 // M
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MagicMapper;
+
+impl MagicMapper {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Component for MagicMapper {}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MeleePowerBonus {
     pub power: i32,
@@ -323,6 +409,17 @@ impl Component for SufferDamage {
 }
 
 // T
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TeleportsEntity;
+
+impl TeleportsEntity {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Component for TeleportsEntity {}
+
 // U
 // V
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
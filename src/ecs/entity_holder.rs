@@ -2,6 +2,10 @@ use legion::entity;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+// セーブデータに無いエンティティを指していた
+#[derive(Debug)]
+pub struct MissingEntity(pub String);
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct EntityHolder {
     #[serde(skip_serializing)]
@@ -27,9 +31,20 @@ impl EntityHolder {
         self.entity_id = Some(format!("{}", self.entity.unwrap()));
     }
 
-    pub fn restore_entity(&mut self, entity_dic: &HashMap<String, entity::Entity>) {
+    pub fn entity_id(&self) -> Option<&String> {
+        self.entity_id.as_ref()
+    }
+
+    pub fn restore_entity(
+        &mut self,
+        entity_dic: &HashMap<String, entity::Entity>,
+    ) -> Result<(), MissingEntity> {
         if let Some(entity_id) = self.entity_id.take() {
-            self.entity = Some(entity_dic.get(&entity_id).unwrap().clone());
+            match entity_dic.get(&entity_id) {
+                Some(entity) => self.entity = Some(*entity),
+                None => return Err(MissingEntity(entity_id)),
+            }
         }
+        Ok(())
     }
 }
@@ -1,5 +1,36 @@
+use crate::dijkstra::DijkstraMap;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct WantsToMove {
     pub x: i32,
     pub y: i32,
 }
+
+// メインメニューに表示するセーブデータを読めなかった理由など
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct MenuMessage {
+    pub text: Option<String>,
+}
+
+// 新しいゲームを始めるときに入力中のシード
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct SeedInput {
+    pub text: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSeed {
+    pub seed: u64,
+}
+
+// プレイヤーが行動した回数。セーブスロットの見出しに出す
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct TurnCount {
+    pub turns: u32,
+}
+
+// プレイヤーまでの歩く距離。毎ターンmap_indexing_systemの後で作り直す
+#[derive(Clone, Default)]
+pub struct DistanceToPlayer {
+    pub map: DijkstraMap,
+}
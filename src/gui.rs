@@ -48,17 +48,19 @@ pub fn draw_ui(world: &World, ctx: &mut Rltk) {
 }
 
 fn draw_tooltips(world: &World, ctx: &mut Rltk) {
-    let map = world.resources.get::<Map>().unwrap();
-
     let mouse_pos = ctx.mouse_pos();
-    if mouse_pos.0 >= map.width as i32 || mouse_pos.1 >= map.height as i32 {
-        return;
-    }
+    let mouse_world_pos = match camera::screen_to_world(world, mouse_pos) {
+        Some(pos) => pos,
+        None => return,
+    };
 
     let mut tooltip: Vec<String> = Vec::new();
 
-    for (name, position) in <(Read<Name>, Read<Position>)>::query().iter_immutable(world) {
-        if position.x == mouse_pos.0 && position.y == mouse_pos.1 {
+    for (name, position) in <(Read<Name>, Read<Position>)>::query()
+        .filter(!component::<Hidden>())
+        .iter_immutable(world)
+    {
+        if position.x == mouse_world_pos.x && position.y == mouse_world_pos.y {
             tooltip.push(name.name.to_string());
         }
     }
@@ -247,7 +249,9 @@ pub fn ranged_target(
         for idx in visible.visible_tiles.iter() {
             let distance = DistanceAlg::Pythagoras.distance2d(player_pos, *idx);
             if distance <= range as f32 {
-                ctx.set_bg(idx.x, idx.y, c(BLUE));
+                if let Some(screen) = camera::world_to_screen(&gs.world, *idx) {
+                    ctx.set_bg(screen.x, screen.y, c(BLUE));
+                }
                 available_cells.push(*idx);
             }
         }
@@ -257,19 +261,17 @@ pub fn ranged_target(
 
     // Draw mouse cursor
     let mouse_pos = ctx.mouse_pos();
+    let mouse_world_pos = camera::screen_to_world(&gs.world, mouse_pos);
     let mut valid_target = false;
     for idx in available_cells.iter() {
-        if idx.x == mouse_pos.0 && idx.y == mouse_pos.1 {
+        if Some(*idx) == mouse_world_pos {
             valid_target = true;
         }
     }
     if valid_target {
         ctx.set_bg(mouse_pos.0, mouse_pos.1, c(CYAN));
         if ctx.left_click {
-            return (
-                ItemMenuResult::Selected,
-                Some(Point::new(mouse_pos.0, mouse_pos.1)),
-            );
+            return (ItemMenuResult::Selected, mouse_world_pos);
         }
     } else {
         ctx.set_bg(mouse_pos.0, mouse_pos.1, c(RED));
@@ -282,11 +284,27 @@ pub fn ranged_target(
 }
 
 pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
-    let save_exists = super::systems::save::save_system::does_save_exist();
+    let save_exists = !gs
+        .world
+        .resources
+        .get::<SaveSlots>()
+        .unwrap()
+        .slots
+        .is_empty();
     let runstate = *gs.world.resources.get::<RunState>().unwrap();
 
     ctx.print_color_centered(15, c(YELLOW), c(BLACK), "Rust Roguelike Tutorial");
 
+    let seed = format!(
+        "Seed: {}",
+        gs.world.resources.get::<GameSeed>().unwrap().seed
+    );
+    ctx.print_color_centered(17, c(GREY), c(BLACK), &seed);
+
+    if let Some(text) = &gs.world.resources.get::<MenuMessage>().unwrap().text {
+        ctx.print_color_centered(20, c(RED), c(BLACK), text);
+    }
+
     if let RunState::MainMenu {
         menu_selection: selection,
     } = runstate
@@ -363,6 +381,200 @@ pub fn c(col: (u8, u8, u8)) -> RGB {
     RGB::named(col)
 }
 
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum SlotMode {
+    Save,
+    Load,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum SlotPickerResult {
+    NoSelection { selected: usize },
+    Selected { path: PathBuf },
+    Cancel,
+}
+
+// 一度に表示するスロットの数。はみ出したら選んでいる所までずらす
+const VISIBLE_SLOTS: usize = 12;
+
+// スロットを選ぶ画面。各スロットは1行目の見出しだけを読んで表示する
+pub fn slot_picker(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    mode: SlotMode,
+    selection: usize,
+) -> SlotPickerResult {
+    let save_directory = gs.world.resources.get::<SaveDirectory>().unwrap();
+    let slots = &gs.world.resources.get::<SaveSlots>().unwrap().slots;
+    let title = match mode {
+        SlotMode::Save => "Save to which slot?",
+        SlotMode::Load => "Load which slot?",
+    };
+    ctx.print_color_centered(8, c(YELLOW), c(BLACK), title);
+    let directory = format!("{}", save_directory.path.display());
+    ctx.print_color_centered(9, c(GREY), c(BLACK), &directory);
+
+    // 保存するときは最後に新しいスロットを選べるようにする
+    let count = match mode {
+        SlotMode::Save => slots.len() + 1,
+        SlotMode::Load => slots.len(),
+    };
+    let first = (selection + 1).saturating_sub(VISIBLE_SLOTS);
+    for (row, i) in (first..count).take(VISIBLE_SLOTS).enumerate() {
+        let y = 12 + row as i32 * 3;
+        let fg = if i == selection { c(MAGENTA) } else { c(WHITE) };
+        match slots.get(i) {
+            Some(slot) => {
+                ctx.print_color(10, y, fg, c(BLACK), &slot.name);
+                match &slot.header {
+                    Ok(header) => {
+                        ctx.print_color(24, y, fg, c(BLACK), &header.summary());
+                        ctx.print_color(24, y + 1, c(GREY), c(BLACK), &header.saved_at());
+                    }
+                    Err(reason) => {
+                        ctx.print_color(24, y, c(RED), c(BLACK), "(unreadable)");
+                        ctx.print_color(24, y + 1, c(GREY), c(BLACK), reason);
+                    }
+                }
+            }
+            None => ctx.print_color(10, y, fg, c(BLACK), "New slot"),
+        }
+    }
+    if count == 0 {
+        ctx.print_color_centered(12, c(WHITE), c(BLACK), "There are no saved games.");
+    }
+    ctx.print_color_centered(48, c(YELLOW), c(BLACK), "ESCAPE to cancel");
+
+    match ctx.key {
+        None => SlotPickerResult::NoSelection {
+            selected: selection,
+        },
+        Some(key) => match key {
+            VirtualKeyCode::Escape => SlotPickerResult::Cancel,
+            VirtualKeyCode::Up if count > 0 => SlotPickerResult::NoSelection {
+                selected: (selection + count - 1) % count,
+            },
+            VirtualKeyCode::Down if count > 0 => SlotPickerResult::NoSelection {
+                selected: (selection + 1) % count,
+            },
+            VirtualKeyCode::Return if selection < count => SlotPickerResult::Selected {
+                path: match slots.get(selection) {
+                    Some(slot) => slot.path.clone(),
+                    None => save_directory.new_slot_path(),
+                },
+            },
+            _ => SlotPickerResult::NoSelection {
+                selected: selection,
+            },
+        },
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum SeedEntryResult {
+    NoSelection,
+    Start { seed: u64 },
+    Cancel,
+}
+
+// u64の最大値の桁数
+const MAX_SEED_DIGITS: usize = 20;
+
+// 新しいゲームのシードを入力する画面。空のまま始めると乱数で決める
+pub fn seed_entry(gs: &mut State, ctx: &mut Rltk) -> SeedEntryResult {
+    let mut input = gs.world.resources.get_mut::<SeedInput>().unwrap();
+
+    ctx.print_color_centered(15, c(YELLOW), c(BLACK), "Enter a seed for the new game");
+    let text = format!("Seed: {}_", input.text);
+    ctx.print_color_centered(18, c(WHITE), c(BLACK), &text);
+    let msg = "Leave it empty for a random seed.";
+    ctx.print_color_centered(20, c(GREY), c(BLACK), msg);
+    let msg = "ENTER to start, ESCAPE to cancel";
+    ctx.print_color_centered(22, c(MAGENTA), c(BLACK), msg);
+
+    match ctx.key {
+        None => SeedEntryResult::NoSelection,
+        Some(key) => match key {
+            VirtualKeyCode::Escape => SeedEntryResult::Cancel,
+            VirtualKeyCode::Back => {
+                input.text.pop();
+                SeedEntryResult::NoSelection
+            }
+            VirtualKeyCode::Return => {
+                if input.text.is_empty() {
+                    return SeedEntryResult::Start {
+                        seed: RandomNumberGenerator::new().next_u64(),
+                    };
+                }
+                // 桁あふれなど数にならないものは受け付けない
+                match input.text.parse::<u64>() {
+                    Ok(seed) => SeedEntryResult::Start { seed },
+                    Err(_) => SeedEntryResult::NoSelection,
+                }
+            }
+            _ => {
+                if let Some(digit) = key_to_digit(key) {
+                    if input.text.len() < MAX_SEED_DIGITS {
+                        input.text.push(digit);
+                    }
+                }
+                SeedEntryResult::NoSelection
+            }
+        },
+    }
+}
+
+fn key_to_digit(key: VirtualKeyCode) -> Option<char> {
+    let digit = match key {
+        VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 => 0,
+        VirtualKeyCode::Key1 | VirtualKeyCode::Numpad1 => 1,
+        VirtualKeyCode::Key2 | VirtualKeyCode::Numpad2 => 2,
+        VirtualKeyCode::Key3 | VirtualKeyCode::Numpad3 => 3,
+        VirtualKeyCode::Key4 | VirtualKeyCode::Numpad4 => 4,
+        VirtualKeyCode::Key5 | VirtualKeyCode::Numpad5 => 5,
+        VirtualKeyCode::Key6 | VirtualKeyCode::Numpad6 => 6,
+        VirtualKeyCode::Key7 | VirtualKeyCode::Numpad7 => 7,
+        VirtualKeyCode::Key8 | VirtualKeyCode::Numpad8 => 8,
+        VirtualKeyCode::Key9 | VirtualKeyCode::Numpad9 => 9,
+        _ => return None,
+    };
+    std::char::from_digit(digit, 10)
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum SaveCorruptedResult {
+    NoSelection,
+    NewGame,
+    BackToMenu,
+}
+
+pub fn save_corrupted(gs: &mut State, ctx: &mut Rltk) -> SaveCorruptedResult {
+    ctx.print_color_centered(15, c(RED), c(BLACK), "The save file is corrupted.");
+    if let Some(text) = &gs.world.resources.get::<MenuMessage>().unwrap().text {
+        ctx.print_color_centered(17, c(WHITE), c(BLACK), text);
+    }
+    let msg = format!(
+        "It has been kept in {}.",
+        gs.world
+            .resources
+            .get::<SaveDirectory>()
+            .unwrap()
+            .path
+            .display()
+    );
+    ctx.print_color_centered(18, c(GREY), c(BLACK), &msg);
+    ctx.print_color_centered(20, c(MAGENTA), c(BLACK), "Start a new game? [Y/N]");
+
+    match ctx.key {
+        None => SaveCorruptedResult::NoSelection,
+        Some(key) => match key {
+            VirtualKeyCode::Y | VirtualKeyCode::Return => SaveCorruptedResult::NewGame,
+            VirtualKeyCode::N | VirtualKeyCode::Escape => SaveCorruptedResult::BackToMenu,
+            _ => SaveCorruptedResult::NoSelection,
+        },
+    }
+}
+
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum GameOverResult {
     NoSelection,
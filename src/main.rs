@@ -8,19 +8,27 @@ use ecs::components::*;
 use ecs::entity_holder::*;
 use ecs::resources::*;
 use ecs::tags::*;
+mod camera;
+mod dijkstra;
 mod map;
 use map::*;
+mod map_builders;
 mod player;
 use player::*;
 mod gamelog;
 use gamelog::*;
 mod gui;
 mod random_table;
+mod raws;
 use random_table::*;
+use raws::{RawMaster, SpawnType};
 mod spawner;
 mod systems;
-use crate::systems::save::save_system::delete_save;
-use systems::save::SaveData;
+use crate::systems::save::load_system;
+use crate::systems::save::save_system::{self, delete_save};
+use std::path::{Path, PathBuf};
+use systems::save::slot::AUTOSAVE_SLOT;
+use systems::save::{dungeon, MasterDungeonMap, SaveData, SaveDirectory, SaveError, SaveSlots};
 use systems::Schedules;
 
 #[derive(PartialEq, Copy, Clone, Debug)]
@@ -38,10 +46,19 @@ pub enum RunState {
     MainMenu {
         menu_selection: gui::MainMenuSelection,
     },
-    SaveGame,
+    SlotPicker {
+        mode: gui::SlotMode,
+        selection: usize,
+    },
     NextLevel,
+    PreviousLevel,
     ShowRemoveItem,
     GameOver,
+    SaveCorrupted,
+    NewGameSeed,
+    MagicMapReveal {
+        row: i32,
+    },
 }
 
 pub struct State {
@@ -58,26 +75,18 @@ impl GameState for State {
             let runstate = self.world.resources.get::<RunState>().unwrap();
             newrunstate = *runstate;
         }
+        let oldrunstate = newrunstate;
         ctx.cls();
 
         match newrunstate {
-            RunState::MainMenu { .. } | RunState::GameOver => {}
+            RunState::MainMenu { .. }
+            | RunState::GameOver
+            | RunState::SaveCorrupted
+            | RunState::NewGameSeed
+            | RunState::SlotPicker { .. } => {}
             _ => {
-                draw_map(&mut self.world, ctx);
-                {
-                    let map = self.world.resources.get::<Map>().unwrap();
-                    let mut data = <(Read<Position>, Read<Renderable>)>::query()
-                        .iter_immutable(&self.world)
-                        .collect::<Vec<_>>();
-                    data.sort_by(|a, b| b.1.render_order.cmp(&a.1.render_order));
-                    for (pos, render) in data.iter() {
-                        let idx = map.xy_idx(pos.x, pos.y);
-                        if map.visible_tiles[idx] {
-                            ctx.set(pos.x, pos.y, render.fg, render.bg, render.glyph)
-                        }
-                    }
-                    gui::draw_ui(&self.world, ctx);
-                }
+                camera::render_camera(&self.world, ctx);
+                gui::draw_ui(&self.world, ctx);
             }
         }
 
@@ -91,10 +100,15 @@ impl GameState for State {
             }
             RunState::PlayerTurn => {
                 self.run_systems();
-                newrunstate = RunState::MonsterTurn;
+                // 地図の巻物を読んだらモンスターの番の前に地図を描き出す
+                newrunstate = match *self.world.resources.get::<RunState>().unwrap() {
+                    RunState::MagicMapReveal { row } => RunState::MagicMapReveal { row },
+                    _ => RunState::MonsterTurn,
+                };
             }
             RunState::MonsterTurn => {
                 self.run_systems();
+                self.world.resources.get_mut::<TurnCount>().unwrap().turns += 1;
                 newrunstate = RunState::AwaitingInput;
             }
             RunState::ShowInventory => {
@@ -163,11 +177,12 @@ impl GameState for State {
                         }
                     }
                     gui::MainMenuResult::Selected { selected } => match selected {
-                        gui::MainMenuSelection::NewGame => newrunstate = RunState::PreRun,
+                        gui::MainMenuSelection::NewGame => newrunstate = self.begin_seed_entry(),
                         gui::MainMenuSelection::LoadGame => {
-                            self.schedules.menu.load.execute(&mut self.world);
-                            newrunstate = RunState::AwaitingInput;
-                            delete_save();
+                            newrunstate = RunState::SlotPicker {
+                                mode: gui::SlotMode::Load,
+                                selection: 0,
+                            }
                         }
                         gui::MainMenuSelection::Quit => {
                             ::std::process::exit(0);
@@ -175,14 +190,39 @@ impl GameState for State {
                     },
                 }
             }
-            RunState::SaveGame => {
-                self.schedules.menu.save.execute(&mut self.world);
-                newrunstate = RunState::MainMenu {
-                    menu_selection: gui::MainMenuSelection::LoadGame,
-                };
+            RunState::SlotPicker { mode, selection } => {
+                match gui::slot_picker(self, ctx, mode, selection) {
+                    gui::SlotPickerResult::NoSelection { selected } => {
+                        newrunstate = RunState::SlotPicker {
+                            mode,
+                            selection: selected,
+                        }
+                    }
+                    gui::SlotPickerResult::Cancel => {
+                        newrunstate = match mode {
+                            gui::SlotMode::Save => RunState::AwaitingInput,
+                            gui::SlotMode::Load => RunState::MainMenu {
+                                menu_selection: gui::MainMenuSelection::LoadGame,
+                            },
+                        }
+                    }
+                    gui::SlotPickerResult::Selected { path } => {
+                        newrunstate = match mode {
+                            gui::SlotMode::Save => self.save_game(&path),
+                            gui::SlotMode::Load => self.load_game(&path),
+                        }
+                    }
+                }
             }
+            // 新しい階の視界を計算してから自動セーブする
             RunState::NextLevel => {
                 self.goto_next_level();
+                self.run_systems();
+                self.autosave();
+                newrunstate = RunState::AwaitingInput;
+            }
+            RunState::PreviousLevel => {
+                self.goto_previous_level();
                 newrunstate = RunState::PreRun;
             }
             RunState::ShowRemoveItem => {
@@ -199,6 +239,42 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::MagicMapReveal { row } => {
+                let mut map = self.world.resources.get_mut::<Map>().unwrap();
+                for x in 0..map.width as i32 {
+                    let idx = map.xy_idx(x, row);
+                    map.revealed_tiles[idx] = true;
+                }
+                newrunstate = if row >= map.height as i32 - 1 {
+                    RunState::MonsterTurn
+                } else {
+                    RunState::MagicMapReveal { row: row + 1 }
+                };
+            }
+            // 壊れたセーブデータは調べられるように消さずに残しておく
+            RunState::SaveCorrupted => match gui::save_corrupted(self, ctx) {
+                gui::SaveCorruptedResult::NoSelection => {}
+                gui::SaveCorruptedResult::NewGame => newrunstate = self.begin_seed_entry(),
+                gui::SaveCorruptedResult::BackToMenu => {
+                    newrunstate = RunState::MainMenu {
+                        menu_selection: gui::MainMenuSelection::NewGame,
+                    };
+                }
+            },
+            RunState::NewGameSeed => match gui::seed_entry(self, ctx) {
+                gui::SeedEntryResult::NoSelection => {}
+                gui::SeedEntryResult::Cancel => {
+                    newrunstate = RunState::MainMenu {
+                        menu_selection: gui::MainMenuSelection::NewGame,
+                    }
+                }
+                gui::SeedEntryResult::Start { seed } => {
+                    // 同じシードでも今の世界を続けず、必ず1階層目から作り直す
+                    self.reset_game(seed);
+                    self.world.resources.insert(MenuMessage::default());
+                    newrunstate = RunState::PreRun;
+                }
+            },
             RunState::GameOver => match gui::game_over(ctx) {
                 gui::GameOverResult::NoSelection => {}
                 gui::GameOverResult::QuitToMenu => {
@@ -213,6 +289,16 @@ impl GameState for State {
             let mut runwriter = self.world.resources.get_mut::<RunState>().unwrap();
             *runwriter = newrunstate;
         }
+        // スロットの一覧は毎フレーム読まず、メニューに入ったときだけ読み直す
+        let entered = std::mem::discriminant(&oldrunstate) != std::mem::discriminant(&newrunstate);
+        if entered
+            && matches!(
+                newrunstate,
+                RunState::MainMenu { .. } | RunState::SlotPicker { .. }
+            )
+        {
+            self.refresh_save_slots();
+        }
         self.schedules.delete_the_dead.execute(&mut self.world);
     }
 }
@@ -222,6 +308,95 @@ impl State {
         self.schedules.main.execute(&mut self.world);
     }
 
+    fn save_game(&mut self, path: &Path) -> RunState {
+        self.schedules.menu.save.execute(&mut self.world);
+        match save_system::write_save(&self.world, path) {
+            Ok(()) => RunState::MainMenu {
+                menu_selection: gui::MainMenuSelection::LoadGame,
+            },
+            Err(e) => {
+                let mut log = self.world.resources.get_mut::<GameLog>().unwrap();
+                log.entries.push(format!("Could not save the game: {}", e));
+                RunState::AwaitingInput
+            }
+        }
+    }
+
+    // 以前の場所にあるセーブデータを移したら、メインメニューで知らせる
+    fn import_legacy_save(&mut self) {
+        let imported = self
+            .world
+            .resources
+            .get::<SaveDirectory>()
+            .unwrap()
+            .import_legacy_save();
+        let text = match imported {
+            Ok(None) => return,
+            Ok(Some(path)) => format!("Moved the old save to {}.", path.display()),
+            Err(e) => format!("Could not move the old save: {}", e),
+        };
+        self.world
+            .resources
+            .insert(MenuMessage { text: Some(text) });
+    }
+
+    fn refresh_save_slots(&mut self) {
+        let slots = self
+            .world
+            .resources
+            .get::<SaveDirectory>()
+            .unwrap()
+            .list_slots();
+        self.world.resources.insert(SaveSlots { slots });
+    }
+
+    fn autosave(&mut self) {
+        let path = self
+            .world
+            .resources
+            .get::<SaveDirectory>()
+            .unwrap()
+            .slot_path(AUTOSAVE_SLOT);
+        self.schedules.menu.save.execute(&mut self.world);
+        if let Err(e) = save_system::write_save(&self.world, &path) {
+            let mut log = self.world.resources.get_mut::<GameLog>().unwrap();
+            log.entries
+                .push(format!("Could not autosave the game: {}", e));
+        }
+    }
+
+    fn load_game(&mut self, path: &Path) -> RunState {
+        let slot = path.file_name().unwrap().to_string_lossy();
+        match load_system::read_save(path) {
+            Ok(save_data) => {
+                self.world.resources.insert(save_data);
+                self.world.resources.insert(MenuMessage::default());
+                self.schedules.menu.load.execute(&mut self.world);
+                if let Err(e) = delete_save(path) {
+                    let mut log = self.world.resources.get_mut::<GameLog>().unwrap();
+                    log.entries
+                        .push(format!("Could not delete the loaded save: {}", e));
+                }
+                RunState::AwaitingInput
+            }
+            // 読めない版は壊れているわけではないのでメニューで知らせるだけにする
+            Err(e @ (SaveError::UnsupportedVersion(_) | SaveError::OutdatedBinary(_))) => {
+                self.world.resources.insert(MenuMessage {
+                    text: Some(format!("{}: {}", slot, e)),
+                });
+                RunState::MainMenu {
+                    menu_selection: gui::MainMenuSelection::LoadGame,
+                }
+            }
+            Err(e) => {
+                self.world.resources.insert(MenuMessage {
+                    text: Some(format!("{}: {}", slot, e)),
+                });
+                RunState::SaveCorrupted
+            }
+        }
+    }
+
     fn entities_to_remove_on_level_change(&mut self) -> Vec<Entity> {
         let mut to_delete: Vec<Entity> = vec![];
         for (entity, (player, in_backpack, equipped)) in
@@ -237,28 +412,8 @@ impl State {
     }
 
     fn goto_next_level(&mut self) {
-        // Delete entities that aren't the player or his/her equipment
-        let to_delete = self.entities_to_remove_on_level_change();
-        for target in to_delete {
-            self.world.delete(target);
-        }
-
-        // Build a new map and place the player
-        let worldmap;
-        let current_depth;
-        {
-            let mut map = self.world.resources.get_mut::<Map>().unwrap();
-            current_depth = map.depth;
-            *map = Map::new_map_rooms_and_corridors(map.depth + 1);
-            worldmap = map.clone();
-        }
-
-        // Spawn bad guys
-        for room in worldmap.rooms.iter().skip(1) {
-            spawner::spawn_room(&mut self.world, room, current_depth + 1);
-        }
-
-        self.initialize_components(worldmap);
+        let player_start = self.change_level(1);
+        self.initialize_components(player_start);
 
         // Notify the player and give them some health
         {
@@ -278,9 +433,66 @@ impl State {
         }
     }
 
-    fn initialize_components(&mut self, worldmap: Map) {
+    fn goto_previous_level(&mut self) {
+        let player_start = self.change_level(-1);
+        self.initialize_components(player_start);
+
+        let mut gamelog = self.world.resources.get_mut::<GameLog>().unwrap();
+        gamelog
+            .entries
+            .push("You ascend to the previous level".to_string());
+    }
+
+    // 今の階層を保存してから隣の階層へ移る。一度訪れた階層はそのまま戻し、
+    // プレイヤーは来た方向の階段の上に置く
+    fn change_level(&mut self, offset: i32) -> Position {
+        dungeon::store_level(&mut self.world);
+
+        // Delete entities that aren't the player or his/her equipment
+        let to_delete = self.entities_to_remove_on_level_change();
+        for target in to_delete {
+            self.world.delete(target);
+        }
+
+        let current_depth = self.world.resources.get::<Map>().unwrap().depth;
+        let new_depth = current_depth + offset;
+        let player_start = if dungeon::restore_level(&mut self.world, new_depth) {
+            None
+        } else {
+            Some(self.generate_world_map(new_depth))
+        };
+
+        let arrival = if offset > 0 {
+            TileType::UpStairs
+        } else {
+            TileType::DownStairs
+        };
+        let map = self.world.resources.get::<Map>().unwrap();
+        map.tiles
+            .iter()
+            .position(|tile| *tile == arrival)
+            .map(|idx| Position::new((idx % map.width) as i32, (idx / map.width) as i32))
+            .or(player_start)
+            .unwrap()
+    }
+
+    fn generate_world_map(&mut self, new_depth: i32) -> Position {
+        let seed = self.world.resources.get::<GameSeed>().unwrap().seed;
+        let mut rng = map_builders::level_rng(seed, new_depth);
+        let mut builder = map_builders::builder_for_depth(new_depth, &mut rng);
+        {
+            let raw_master = self.world.resources.get::<RawMaster>().unwrap();
+            let spawn_table = raw_master.spawn_table_for_depth(new_depth);
+            builder.build_map(&mut rng, &raw_master, &spawn_table);
+        }
+        self.world.resources.insert(builder.get_map());
+        builder.spawn_entities(&mut self.world);
+        builder.get_starting_position()
+    }
+
+    fn initialize_components(&mut self, player_start: Position) {
         // Place the player and update resources
-        let (player_x, player_y) = worldmap.rooms[0].center();
+        let (player_x, player_y) = (player_start.x, player_start.y);
         {
             let mut player_position = self.world.resources.get_mut::<Point>().unwrap();
             *player_position = Point::new(player_x, player_y);
@@ -304,7 +516,21 @@ impl State {
         }
     }
 
+    // 新しく始める前にシードを入力してもらう。初期値は今の世界のシード
+    fn begin_seed_entry(&mut self) -> RunState {
+        let seed = self.world.resources.get::<GameSeed>().unwrap().seed;
+        self.world.resources.insert(SeedInput {
+            text: seed.to_string(),
+        });
+        RunState::NewGameSeed
+    }
+
     fn game_over_cleanup(&mut self) {
+        self.reset_game(new_game_seed());
+    }
+
+    // すべてのエンティティを消し、指定したシードで1階層目から作り直す
+    fn reset_game(&mut self, seed: u64) {
         let mut to_delete: Vec<Entity> = vec![];
         for (entity, _) in <TryRead<Name>>::query().iter_entities(&mut self.world) {
             to_delete.push(entity);
@@ -314,25 +540,33 @@ impl State {
             self.world.delete(target);
         }
 
-        let worldmap;
-        {
-            let mut map_resource = self.world.resources.get_mut::<Map>().unwrap();
-            *map_resource = Map::new_map_rooms_and_corridors(1);
-            worldmap = map_resource.clone();
-        }
-
-        for room in worldmap.rooms.iter().skip(1) {
-            spawner::spawn_room(&mut self.world, room, 1);
-        }
+        insert_seed(&mut self.world, seed);
+        self.world.resources.insert(MasterDungeonMap::default());
+        self.world.resources.insert(TurnCount::default());
 
-        let (player_x, player_y) = worldmap.rooms[0].center();
-        let player_entity = spawner::player(&mut self.world, player_x, player_y);
+        let player_start = self.generate_world_map(1);
+        let player_entity = spawner::player(&mut self.world, player_start.x, player_start.y);
         self.world.resources.insert(player_entity);
 
-        self.initialize_components(worldmap);
+        self.initialize_components(player_start);
     }
 }
 
+// `--seed <数値>`で起動すると同じダンジョンを再現できる
+fn new_game_seed() -> u64 {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == "--seed")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|seed| seed.parse::<u64>().ok())
+        .unwrap_or_else(|| RandomNumberGenerator::new().next_u64())
+}
+
+fn insert_seed(world: &mut World, seed: u64) {
+    world.resources.insert(GameSeed { seed });
+    world.resources.insert(RandomNumberGenerator::seeded(seed));
+}
+
 fn main() {
     use rltk::RltkBuilder;
 
@@ -347,19 +581,18 @@ fn main() {
         schedules: systems::build_schedules(),
     };
 
-    gs.world
-        .resources
-        .insert(rltk::RandomNumberGenerator::new());
-
-    let map = Map::new_map_rooms_and_corridors(1);
-    let (player_x, player_y) = map.rooms[0].center();
-    gs.world.resources.insert(Point::new(player_x, player_y));
+    let raw_master = match raws::load_raws(raws::RAWS_PATH) {
+        Ok(raw_master) => raw_master,
+        Err(e) => panic!("{}", e),
+    };
+    gs.world.resources.insert(raw_master);
 
-    for room in map.rooms.iter().skip(1) {
-        spawner::spawn_room(&mut gs.world, room, 1);
-    }
+    insert_seed(&mut gs.world, new_game_seed());
+    gs.world.resources.insert(MasterDungeonMap::default());
 
-    gs.world.resources.insert(map);
+    let player_start = gs.generate_world_map(1);
+    let (player_x, player_y) = (player_start.x, player_start.y);
+    gs.world.resources.insert(Point::new(player_x, player_y));
 
     let player_entity = spawner::player(&mut gs.world, player_x, player_y);
 
@@ -372,7 +605,13 @@ fn main() {
         menu_selection: gui::MainMenuSelection::NewGame,
     });
     gs.world.resources.insert(WantsToMove { x: 0, y: 0 });
+    gs.world.resources.insert(DistanceToPlayer::default());
     gs.world.resources.insert(SaveData::default());
+    gs.world.resources.insert(MenuMessage::default());
+    gs.world.resources.insert(SaveDirectory::from_args());
+    gs.import_legacy_save();
+    gs.refresh_save_slots();
+    gs.world.resources.insert(TurnCount::default());
 
     spawner::debug_all_item(&mut gs.world, player_x, player_y);
 
@@ -1,34 +1,74 @@
 use super::Rect;
 use legion::prelude::*;
-use rltk::{Algorithm2D, BaseMap, Console, Point, RandomNumberGenerator, Rltk, RGB};
+use rltk::{Algorithm2D, BaseMap, Point};
 use serde::{Deserialize, Serialize};
-use std::cmp::{max, min};
 
-pub const MAPWIDTH: usize = 80;
-pub const MAPHEIGHT: usize = 43;
-pub const MAPCOUNT: usize = MAPWIDTH * MAPHEIGHT;
+// マップの既定の大きさ。画面より大きいのでカメラで一部だけを表示する
+pub const MAPWIDTH: usize = 200;
+pub const MAPHEIGHT: usize = 120;
 
-#[derive(Serialize, Deserialize, PartialEq, Copy, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Copy, Clone, Debug)]
 pub enum TileType {
     Wall,
     Floor,
     DownStairs,
+    UpStairs,
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Bridge,
+    Grass,
+    Gravel,
+}
+
+impl TileType {
+    pub fn is_walkable(self) -> bool {
+        !matches!(self, TileType::Wall | TileType::DeepWater)
+    }
+
+    pub fn is_opaque(self) -> bool {
+        self == TileType::Wall
+    }
+
+    // 踏むとダメージを受ける。モンスターは経路に選ばない
+    pub fn is_hazardous(self) -> bool {
+        self == TileType::Lava
+    }
+
+    // 隣のタイルへ入るときの移動コストの倍率
+    pub fn movement_cost(self) -> f32 {
+        match self {
+            TileType::ShallowWater => 2.0,
+            TileType::Gravel => 1.5,
+            TileType::Grass => 1.1,
+            _ => 1.0,
+        }
+    }
 }
 
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct Map {
+    #[serde(with = "crate::systems::save::format::rle")]
     pub tiles: Vec<TileType>,
     pub rooms: Vec<Rect>,
     pub width: usize,
     pub height: usize,
+    #[serde(with = "crate::systems::save::format::rle")]
     pub revealed_tiles: Vec<bool>,
+    #[serde(with = "crate::systems::save::format::rle")]
     pub visible_tiles: Vec<bool>,
+    #[serde(with = "crate::systems::save::format::rle")]
     pub blocked: Vec<bool>,
     pub depth: i32,
 
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
     pub tile_content: Vec<Vec<Entity>>,
+
+    // 閉じたドアなど視界をさえぎるエンティティがいるタイル。map_indexing_systemが更新する
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub view_blocked: Vec<bool>,
 }
 
 impl Map {
@@ -36,34 +76,6 @@ impl Map {
         (y as usize * self.width as usize) + x as usize
     }
 
-    fn apply_room_to_map(&mut self, room: &Rect) {
-        for y in room.y1 + 1..=room.y2 {
-            for x in room.x1 + 1..=room.x2 {
-                let idx = self.xy_idx(x, y);
-                self.tiles[idx] = TileType::Floor;
-            }
-        }
-    }
-
-    fn apply_horizontal_tunnel(&mut self, x1: i32, x2: i32, y: i32) {
-        for x in min(x1, x2)..=max(x1, x2) {
-            self.attach_tile(x, y)
-        }
-    }
-
-    fn apply_vertical_tunnel(&mut self, y1: i32, y2: i32, x: i32) {
-        for y in min(y1, y2)..=max(y1, y2) {
-            self.attach_tile(x, y)
-        }
-    }
-
-    fn attach_tile(&mut self, x: i32, y: i32) -> () {
-        let idx = self.xy_idx(x, y);
-        if idx > 0 && idx < self.width as usize * self.height as usize {
-            self.tiles[idx as usize] = TileType::Floor;
-        }
-    }
-
     fn is_exit_valid(&self, x: usize, y: usize) -> bool {
         if x < 1 || x > (self.width - 1) as usize || y < 1 || y > (self.height - 1) as usize {
             return false;
@@ -74,7 +86,7 @@ impl Map {
 
     pub fn populate_blocked(&mut self) {
         for (i, tile) in self.tiles.iter_mut().enumerate() {
-            self.blocked[i] = *tile == TileType::Wall;
+            self.blocked[i] = !tile.is_walkable();
         }
     }
 
@@ -82,64 +94,25 @@ impl Map {
         for content in self.tile_content.iter_mut() {
             content.clear();
         }
+        for view_blocked in self.view_blocked.iter_mut() {
+            *view_blocked = false;
+        }
     }
 
-    pub fn new_map_rooms_and_corridors(new_depth: i32) -> Map {
-        let mut map = Map {
-            tiles: vec![TileType::Wall; MAPCOUNT],
+    pub fn new(new_depth: i32, width: usize, height: usize) -> Map {
+        let map_count = width * height;
+        Map {
+            tiles: vec![TileType::Wall; map_count],
             rooms: Vec::new(),
-            width: MAPWIDTH,
-            height: MAPHEIGHT,
-            revealed_tiles: vec![false; MAPCOUNT],
-            visible_tiles: vec![false; MAPCOUNT],
-            blocked: vec![false; MAPCOUNT],
-            tile_content: vec![Vec::new(); MAPCOUNT],
+            width,
+            height,
+            revealed_tiles: vec![false; map_count],
+            visible_tiles: vec![false; map_count],
+            blocked: vec![false; map_count],
+            tile_content: vec![Vec::new(); map_count],
+            view_blocked: vec![false; map_count],
             depth: new_depth,
-        };
-
-        const MAX_ROOMS: i32 = 30;
-        const MIN_SIZE: i32 = 6;
-        const MAX_SIZE: i32 = 10;
-        let mut rng = RandomNumberGenerator::new();
-
-        for _ in 0..MAX_ROOMS {
-            let w = rng.range(MIN_SIZE, MAX_SIZE) as usize;
-            let h = rng.range(MIN_SIZE, MAX_SIZE) as usize;
-            let x = rng.roll_dice(1, (map.width - w - 1) as i32) - 1;
-            let y = rng.roll_dice(1, (map.height - h - 1) as i32) - 1;
-            let new_room = Rect::new(x, y, w as i32, h as i32);
-            let mut ok = true;
-            for other_room in map.rooms.iter() {
-                if new_room.intersect(other_room) {
-                    ok = false
-                }
-            }
-            if ok {
-                map.apply_room_to_map(&new_room);
-
-                if !map.rooms.is_empty() {
-                    let (new_x, new_y) = new_room.center();
-                    let (prev_x, prev_y) = map.rooms[map.rooms.len() - 1].center();
-                    if rng.range(0, 2) == 1 {
-                        map.apply_horizontal_tunnel(prev_x, new_x, prev_y);
-                        map.apply_vertical_tunnel(prev_y, new_y, new_x);
-                    } else {
-                        map.apply_vertical_tunnel(prev_y, new_y, prev_x);
-                        map.apply_horizontal_tunnel(prev_x, new_x, new_y);
-                    }
-                }
-
-                map.rooms.push(new_room);
-            }
         }
-
-        // TODO
-        // let stairs_position = map.rooms[map.rooms.len() - 1].center();
-        let stairs_position = map.rooms[0].center();
-        let stairs_idx = map.xy_idx(stairs_position.0, stairs_position.1);
-        map.tiles[stairs_idx] = TileType::DownStairs;
-
-        map
     }
 }
 
@@ -151,80 +124,32 @@ impl Algorithm2D for Map {
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx as usize] == TileType::Wall
+        self.tiles[idx].is_opaque() || self.view_blocked[idx]
     }
 
     fn get_available_exits(&self, idx: usize) -> Vec<(usize, f32)> {
         let mut exits: Vec<(usize, f32)> = Vec::new();
         let x = idx % self.width as usize;
         let y = idx / self.width as usize;
+        let mut add_exit = |x: usize, y: usize, cost: f32| {
+            if self.is_exit_valid(x, y) {
+                let next = self.xy_idx(x as i32, y as i32);
+                exits.push((next, cost * self.tiles[next].movement_cost()));
+            }
+        };
 
         // Cardinal directions
-        if self.is_exit_valid(x - 1, y) {
-            exits.push((idx - 1, 1.0))
-        };
-        if self.is_exit_valid(x + 1, y) {
-            exits.push((idx + 1, 1.0))
-        };
-        if self.is_exit_valid(x, y - 1) {
-            exits.push((idx - self.width, 1.0))
-        };
-        if self.is_exit_valid(x, y + 1) {
-            exits.push((idx + self.width, 1.0))
-        };
+        add_exit(x - 1, y, 1.0);
+        add_exit(x + 1, y, 1.0);
+        add_exit(x, y - 1, 1.0);
+        add_exit(x, y + 1, 1.0);
 
         // Diagonals
-        if self.is_exit_valid(x - 1, y - 1) {
-            exits.push(((idx - self.width) - 1, 1.45));
-        }
-        if self.is_exit_valid(x + 1, y - 1) {
-            exits.push(((idx - self.width) + 1, 1.45));
-        }
-        if self.is_exit_valid(x - 1, y + 1) {
-            exits.push(((idx + self.width) - 1, 1.45));
-        }
-        if self.is_exit_valid(x + 1, y + 1) {
-            exits.push(((idx + self.width) + 1, 1.45));
-        }
+        add_exit(x - 1, y - 1, 1.45);
+        add_exit(x + 1, y - 1, 1.45);
+        add_exit(x - 1, y + 1, 1.45);
+        add_exit(x + 1, y + 1, 1.45);
 
         exits
     }
 }
-
-pub fn draw_map(world: &mut World, ctx: &mut Rltk) {
-    let map = world.resources.get::<Map>().unwrap();
-
-    let mut y = 0;
-    let mut x = 0;
-    for (idx, tile) in map.tiles.iter().enumerate() {
-        if map.revealed_tiles[idx] {
-            let glyph;
-            let mut fg;
-            match tile {
-                TileType::Floor => {
-                    glyph = rltk::to_cp437('.');
-                    fg = RGB::from_f32(0.0, 0.5, 0.5);
-                }
-                TileType::Wall => {
-                    glyph = rltk::to_cp437('#');
-                    fg = RGB::from_f32(0., 1.0, 0.);
-                }
-                TileType::DownStairs => {
-                    glyph = rltk::to_cp437('>');
-                    fg = RGB::from_f32(0.0, 1.0, 1.0);
-                }
-            }
-            if !map.visible_tiles[idx] {
-                fg = fg.to_greyscale()
-            }
-            ctx.set(x, y, fg, RGB::from_f32(0., 0., 0.), glyph);
-        }
-
-        // Move the coordinates
-        x += 1;
-        if x > 79 {
-            x = 0;
-            y += 1;
-        }
-    }
-}
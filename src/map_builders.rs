@@ -0,0 +1,165 @@
+use super::*;
+mod bsp_dungeon;
+use bsp_dungeon::BspDungeonBuilder;
+mod cellular_automata;
+use cellular_automata::CellularAutomataBuilder;
+mod common;
+use common::*;
+mod distant_stairs;
+use distant_stairs::DistantStairsBuilder;
+mod door_placement;
+use door_placement::DoorPlacementBuilder;
+mod dla;
+use dla::DLABuilder;
+mod drunkard;
+use drunkard::DrunkardsWalkBuilder;
+mod maze;
+use maze::MazeBuilder;
+mod prefab_builder;
+use prefab_builder::PrefabBuilder;
+mod river;
+use river::RiverBuilder;
+mod simple_map;
+use simple_map::SimpleMapBuilder;
+mod town;
+use town::TownBuilder;
+mod waveform_collapse;
+use waveform_collapse::WaveformCollapseBuilder;
+
+pub trait MapBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    );
+    fn get_map(&self) -> Map;
+    fn get_starting_position(&self) -> Position;
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)>;
+
+    fn spawn_entities(&mut self, world: &mut World) {
+        for entity in self.get_spawn_list().iter() {
+            spawner::spawn_entity(world, entity);
+        }
+    }
+}
+
+// 出現表を使わずにビルダーが名前で置くもの。RawMasterを作るときに定義があるか確かめる
+pub fn spawn_names() -> Vec<&'static str> {
+    prefab_builder::spawn_names()
+        .chain(town::SPAWN_NAMES.iter().copied())
+        .chain(std::iter::once(door_placement::DOOR))
+        .collect()
+}
+
+// この階層はダンジョンの入口がある町になる
+const TOWN_DEPTH: i32 = 1;
+
+// この間隔ごとの階層は迷路になる
+const LABYRINTH_INTERVAL: i32 = 5;
+
+// 迷路以外の階層は1/RIVER_CHANCEの確率で川が流れる
+const RIVER_CHANCE: i32 = 3;
+
+// 深さごとに使うビルダーを選ぶ
+pub fn builder_for_depth(new_depth: i32, rng: &mut RandomNumberGenerator) -> Box<dyn MapBuilder> {
+    let builder: Box<dyn MapBuilder> = match prefab_builder::level_for_depth(new_depth) {
+        _ if new_depth == TOWN_DEPTH => Box::new(TownBuilder::new(new_depth)),
+        Some(level) => Box::new(PrefabBuilder::constant(new_depth, level)),
+        None => {
+            let builder = Box::new(PrefabBuilder::room_vaults(
+                new_depth,
+                random_builder(new_depth, rng),
+            ));
+            if new_depth % LABYRINTH_INTERVAL != 0 && rng.roll_dice(1, RIVER_CHANCE) == 1 {
+                Box::new(RiverBuilder::new(new_depth, builder))
+            } else {
+                builder
+            }
+        }
+    };
+
+    let builder = Box::new(DoorPlacementBuilder::new(new_depth, builder));
+    Box::new(DistantStairsBuilder::new(new_depth, builder))
+}
+
+fn random_builder(new_depth: i32, rng: &mut RandomNumberGenerator) -> Box<dyn MapBuilder> {
+    if new_depth % LABYRINTH_INTERVAL == 0 {
+        return Box::new(MazeBuilder::new(new_depth, 10));
+    }
+
+    match rng.roll_dice(1, 11) {
+        1 => Box::new(SimpleMapBuilder::new(new_depth)),
+        2 => Box::new(BspDungeonBuilder::new(new_depth)),
+        3 => Box::new(CellularAutomataBuilder::new(new_depth)),
+        4 => Box::new(DrunkardsWalkBuilder::single_start(new_depth)),
+        5 => Box::new(DrunkardsWalkBuilder::random_starts(new_depth)),
+        6 => Box::new(DrunkardsWalkBuilder::central_starts(new_depth)),
+        7 => Box::new(DLABuilder::walk_inwards(new_depth)),
+        8 => Box::new(DLABuilder::walk_outwards(new_depth)),
+        9 => Box::new(DLABuilder::central_attractor(new_depth)),
+        10 => Box::new(WaveformCollapseBuilder::derived_map(
+            new_depth,
+            Box::new(CellularAutomataBuilder::new(new_depth)),
+        )),
+        _ => Box::new(WaveformCollapseBuilder::derived_map(
+            new_depth,
+            Box::new(PrefabBuilder::constant(
+                new_depth,
+                prefab_builder::ORC_FORTRESS,
+            )),
+        )),
+    }
+}
+
+// 同じシードと深さからは必ず同じ乱数列が得られるようにする
+pub fn level_rng(seed: u64, depth: i32) -> RandomNumberGenerator {
+    RandomNumberGenerator::seeded(seed ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(
+        seed: u64,
+        depth: i32,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) -> (Map, Position, Vec<(usize, SpawnType)>) {
+        let mut rng = level_rng(seed, depth);
+        let mut builder = builder_for_depth(depth, &mut rng);
+        builder.build_map(&mut rng, raws, spawn_table);
+        (
+            builder.get_map(),
+            builder.get_starting_position(),
+            builder.get_spawn_list().clone(),
+        )
+    }
+
+    // 深さで町、固定マップ、迷路、溶岩の川と使うビルダーが変わるので全部の深さを試す
+    #[test]
+    fn same_seed_and_depth_generate_the_same_level() {
+        let raw_master = crate::raws::load_raws(crate::raws::RAWS_PATH).unwrap();
+        for seed in 0..3 {
+            for depth in 1..=11 {
+                let spawn_table = raw_master.spawn_table_for_depth(depth);
+                let (map, start, spawns) = generate(seed, depth, &raw_master, &spawn_table);
+                let (other_map, other_start, other_spawns) =
+                    generate(seed, depth, &raw_master, &spawn_table);
+                assert_eq!(map.tiles, other_map.tiles, "seed {} depth {}", seed, depth);
+                assert_eq!(start, other_start, "seed {} depth {}", seed, depth);
+                assert_eq!(spawns, other_spawns, "seed {} depth {}", seed, depth);
+            }
+        }
+    }
+
+    #[test]
+    fn different_seeds_generate_different_levels() {
+        let raw_master = crate::raws::load_raws(crate::raws::RAWS_PATH).unwrap();
+        let spawn_table = RandomTable::new();
+        let (map, _, _) = generate(1, 2, &raw_master, &spawn_table);
+        let (other_map, _, _) = generate(2, 2, &raw_master, &spawn_table);
+        assert_ne!(map.tiles, other_map.tiles);
+    }
+}
@@ -0,0 +1,159 @@
+use super::*;
+
+const MIN_LEAF_SIZE: i32 = 8;
+const MAX_LEAF_SIZE: i32 = 20;
+const MIN_ROOM_SIZE: i32 = 4;
+const MAX_ROOM_SIZE: i32 = 12;
+
+pub struct BspDungeonBuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    spawn_list: Vec<(usize, SpawnType)>,
+}
+
+impl MapBuilder for BspDungeonBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        _raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl BspDungeonBuilder {
+    pub fn new(new_depth: i32) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            depth: new_depth,
+            spawn_list: Vec::new(),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator, spawn_table: &RandomTable<SpawnType>) {
+        // 外周の1マスは壁のまま残す
+        let root = Rect {
+            x1: 0,
+            y1: 0,
+            x2: self.map.width as i32 - 1,
+            y2: self.map.height as i32 - 1,
+        };
+        self.partition(rng, &root);
+
+        let (start_x, start_y) = self.map.rooms[0].center();
+        self.starting_position = Position::new(start_x, start_y);
+
+        for room in self.map.rooms.iter().skip(1) {
+            spawner::spawn_room(
+                &self.map,
+                rng,
+                room,
+                self.depth,
+                spawn_table,
+                &mut self.spawn_list,
+            );
+        }
+    }
+
+    // 区画を再帰的に分割し、葉ごとに部屋を1つ掘る。
+    // 戻り値は兄弟の区画と通路でつなぐための、この区画内の部屋
+    fn partition(&mut self, rng: &mut RandomNumberGenerator, area: &Rect) -> Rect {
+        let width = area.x2 - area.x1;
+        let height = area.y2 - area.y1;
+        let can_split_x = width >= MIN_LEAF_SIZE * 2;
+        let can_split_y = height >= MIN_LEAF_SIZE * 2;
+        let small_enough = width <= MAX_LEAF_SIZE && height <= MAX_LEAF_SIZE;
+
+        if (!can_split_x && !can_split_y) || (small_enough && rng.roll_dice(1, 4) == 1) {
+            return self.carve_room(rng, area);
+        }
+
+        let split_x = if can_split_x && can_split_y {
+            if width as f32 > height as f32 * 1.25 {
+                true
+            } else if height as f32 > width as f32 * 1.25 {
+                false
+            } else {
+                rng.range(0, 2) == 1
+            }
+        } else {
+            can_split_x
+        };
+
+        let (first, second) = if split_x {
+            let at = area.x1 + rng.range(MIN_LEAF_SIZE, width - MIN_LEAF_SIZE + 1);
+            (
+                Rect {
+                    x2: at,
+                    ..area.clone()
+                },
+                Rect {
+                    x1: at,
+                    ..area.clone()
+                },
+            )
+        } else {
+            let at = area.y1 + rng.range(MIN_LEAF_SIZE, height - MIN_LEAF_SIZE + 1);
+            (
+                Rect {
+                    y2: at,
+                    ..area.clone()
+                },
+                Rect {
+                    y1: at,
+                    ..area.clone()
+                },
+            )
+        };
+
+        let first_room = self.partition(rng, &first);
+        let second_room = self.partition(rng, &second);
+
+        let (first_x, first_y) = first_room.center();
+        let (second_x, second_y) = second_room.center();
+        if rng.range(0, 2) == 1 {
+            apply_horizontal_tunnel(&mut self.map, first_x, second_x, first_y);
+            apply_vertical_tunnel(&mut self.map, first_y, second_y, second_x);
+        } else {
+            apply_vertical_tunnel(&mut self.map, first_y, second_y, first_x);
+            apply_horizontal_tunnel(&mut self.map, first_x, second_x, second_y);
+        }
+
+        if rng.range(0, 2) == 1 {
+            first_room
+        } else {
+            second_room
+        }
+    }
+
+    // 隣の区画の部屋とくっつかないよう、区画の右端と下端の1マスは壁に残す
+    fn carve_room(&mut self, rng: &mut RandomNumberGenerator, leaf: &Rect) -> Rect {
+        let leaf_width = leaf.x2 - leaf.x1;
+        let leaf_height = leaf.y2 - leaf.y1;
+
+        let w = rng.range(MIN_ROOM_SIZE, i32::min(MAX_ROOM_SIZE, leaf_width - 1) + 1);
+        let h = rng.range(MIN_ROOM_SIZE, i32::min(MAX_ROOM_SIZE, leaf_height - 1) + 1);
+        let x = leaf.x1 + rng.range(0, leaf_width - w);
+        let y = leaf.y1 + rng.range(0, leaf_height - h);
+
+        let room = Rect::new(x, y, w, h);
+        apply_room_to_map(&mut self.map, &room);
+        self.map.rooms.push(room.clone());
+        room
+    }
+}
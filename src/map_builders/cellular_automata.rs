@@ -0,0 +1,111 @@
+use super::*;
+
+const ITERATIONS: i32 = 15;
+const WALL_PERCENT: i32 = 55;
+
+pub struct CellularAutomataBuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    spawn_list: Vec<(usize, SpawnType)>,
+}
+
+impl MapBuilder for CellularAutomataBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        _raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl CellularAutomataBuilder {
+    pub fn new(new_depth: i32) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            depth: new_depth,
+            spawn_list: Vec::new(),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator, spawn_table: &RandomTable<SpawnType>) {
+        // 外周以外をランダムに埋める
+        for y in 1..self.map.height as i32 - 1 {
+            for x in 1..self.map.width as i32 - 1 {
+                let idx = self.map.xy_idx(x, y);
+                self.map.tiles[idx] = if rng.roll_dice(1, 100) > WALL_PERCENT {
+                    TileType::Floor
+                } else {
+                    TileType::Wall
+                };
+            }
+        }
+
+        for _ in 0..ITERATIONS {
+            self.smooth();
+        }
+
+        // つながっていない洞窟は壁に戻す
+        let region = largest_region(&self.map);
+        keep_only_region(&mut self.map, &region);
+
+        let center = Point::new(self.map.width / 2, self.map.height / 2);
+        let start_idx = nearest_tile(&self.map, &region, center);
+        self.starting_position = idx_to_position(&self.map, start_idx);
+
+        let noise_areas = generate_voronoi_spawn_regions(&self.map, rng);
+        spawn_noise_areas(
+            &self.map,
+            rng,
+            &noise_areas,
+            &self.starting_position,
+            self.depth,
+            spawn_table,
+            &mut self.spawn_list,
+        );
+    }
+
+    // 周囲8マスの壁が5以上、または0なら壁、それ以外は床にする
+    fn smooth(&mut self) {
+        let mut new_tiles = self.map.tiles.clone();
+
+        for y in 1..self.map.height as i32 - 1 {
+            for x in 1..self.map.width as i32 - 1 {
+                let mut neighbors = 0;
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        if (dx != 0 || dy != 0)
+                            && self.map.tiles[self.map.xy_idx(x + dx, y + dy)] == TileType::Wall
+                        {
+                            neighbors += 1;
+                        }
+                    }
+                }
+
+                let idx = self.map.xy_idx(x, y);
+                new_tiles[idx] = if neighbors > 4 || neighbors == 0 {
+                    TileType::Wall
+                } else {
+                    TileType::Floor
+                };
+            }
+        }
+
+        self.map.tiles = new_tiles;
+    }
+}
@@ -0,0 +1,217 @@
+use super::*;
+use std::cmp::{max, min};
+use std::collections::HashMap;
+
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+pub fn apply_room_to_map(map: &mut Map, room: &Rect) {
+    for y in room.y1 + 1..=room.y2 {
+        for x in room.x1 + 1..=room.x2 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+pub fn apply_horizontal_tunnel(map: &mut Map, x1: i32, x2: i32, y: i32) {
+    for x in min(x1, x2)..=max(x1, x2) {
+        attach_tile(map, x, y)
+    }
+}
+
+pub fn apply_vertical_tunnel(map: &mut Map, y1: i32, y2: i32, x: i32) {
+    for y in min(y1, y2)..=max(y1, y2) {
+        attach_tile(map, x, y)
+    }
+}
+
+fn attach_tile(map: &mut Map, x: i32, y: i32) {
+    let idx = map.xy_idx(x, y);
+    if idx > 0 && idx < map.width * map.height {
+        map.tiles[idx] = TileType::Floor;
+    }
+}
+
+// 床を8近傍でつないだ連結成分のうち最大のものを返す
+pub fn largest_region(map: &Map) -> Vec<usize> {
+    let mut visited = vec![false; map.tiles.len()];
+    let mut largest: Vec<usize> = Vec::new();
+
+    for start in 0..map.tiles.len() {
+        if visited[start] || !map.tiles[start].is_walkable() {
+            continue;
+        }
+
+        let region = flood_fill(map, start, &mut visited);
+        if region.len() > largest.len() {
+            largest = region;
+        }
+    }
+
+    largest
+}
+
+// startから8近傍でたどれる床を返す
+pub fn connected_region(map: &Map, start: usize) -> Vec<usize> {
+    let mut visited = vec![false; map.tiles.len()];
+    flood_fill(map, start, &mut visited)
+}
+
+fn flood_fill(map: &Map, start: usize, visited: &mut [bool]) -> Vec<usize> {
+    let mut region = Vec::new();
+    let mut open_list = vec![start];
+    visited[start] = true;
+
+    while let Some(idx) = open_list.pop() {
+        region.push(idx);
+        let x = (idx % map.width) as i32;
+        let y = (idx / map.width) as i32;
+        for (dx, dy) in NEIGHBOURS.iter() {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || ny < 0 || nx >= map.width as i32 || ny >= map.height as i32 {
+                continue;
+            }
+            let next = map.xy_idx(nx, ny);
+            if !visited[next] && map.tiles[next].is_walkable() {
+                visited[next] = true;
+                open_list.push(next);
+            }
+        }
+    }
+
+    region
+}
+
+// 歩けるすべてのタイルにstartからたどり着けるか
+pub fn is_fully_connected(map: &Map, start: usize) -> bool {
+    let open_tiles = map.tiles.iter().filter(|tile| tile.is_walkable()).count();
+    connected_region(map, start).len() == open_tiles
+}
+
+// startから各タイルまでの歩数。たどり着けないタイルはf32::MAXになる
+pub fn dijkstra_distances(map: &Map, start: usize) -> Vec<f32> {
+    dijkstra::DijkstraMap::new(map, &[start])
+        .distances()
+        .to_vec()
+}
+
+pub fn nearest_tile(map: &Map, region: &[usize], point: Point) -> usize {
+    *region
+        .iter()
+        .min_by_key(|idx| distance_from(map, point, **idx) as i32)
+        .unwrap()
+}
+
+fn distance_from(map: &Map, from: Point, idx: usize) -> f32 {
+    let to = Point::new(idx % map.width, idx / map.width);
+    rltk::DistanceAlg::Pythagoras.distance2d(from, to)
+}
+
+pub fn idx_to_position(map: &Map, idx: usize) -> Position {
+    Position::new((idx % map.width) as i32, (idx / map.width) as i32)
+}
+
+// 指定した領域以外の床を壁に戻す
+pub fn keep_only_region(map: &mut Map, region: &[usize]) {
+    let mut keep = vec![false; map.tiles.len()];
+    for idx in region.iter() {
+        keep[*idx] = true;
+    }
+    for (idx, tile) in map.tiles.iter_mut().enumerate() {
+        if !keep[idx] {
+            *tile = TileType::Wall;
+        }
+    }
+}
+
+// 部屋のないマップ向けに、セルラーノイズで床をいくつかの区域に分ける
+pub fn generate_voronoi_spawn_regions(
+    map: &Map,
+    rng: &mut RandomNumberGenerator,
+) -> HashMap<i32, Vec<usize>> {
+    let mut noise_areas: HashMap<i32, Vec<usize>> = HashMap::new();
+    let mut noise = rltk::FastNoise::seeded(rng.roll_dice(1, 65536) as u64);
+    noise.set_noise_type(rltk::NoiseType::Cellular);
+    noise.set_frequency(0.08);
+    noise.set_cellular_distance_function(rltk::CellularDistanceFunction::Manhattan);
+
+    for y in 1..map.height as i32 - 1 {
+        for x in 1..map.width as i32 - 1 {
+            let idx = map.xy_idx(x, y);
+            if map.tiles[idx] == TileType::Floor {
+                let cell_value_f = noise.get_noise(x as f32, y as f32) * 10240.0;
+                let cell_value = cell_value_f as i32;
+                noise_areas.entry(cell_value).or_default().push(idx);
+            }
+        }
+    }
+
+    noise_areas
+}
+
+// プレイヤーの開始位置を除いて、区域ごとにモンスターとアイテムを置く
+pub fn spawn_noise_areas(
+    map: &Map,
+    rng: &mut RandomNumberGenerator,
+    noise_areas: &HashMap<i32, Vec<usize>>,
+    starting_position: &Position,
+    depth: i32,
+    spawn_table: &RandomTable<SpawnType>,
+    spawn_list: &mut Vec<(usize, SpawnType)>,
+) {
+    let start_idx = map.xy_idx(starting_position.x, starting_position.y);
+
+    // HashMapの順番は実行ごとに変わるので、シードから同じ結果になるよう区域の番号順に処理する
+    let mut area_ids: Vec<&i32> = noise_areas.keys().collect();
+    area_ids.sort();
+    for area in area_ids.iter().map(|id| &noise_areas[id]) {
+        let area: Vec<usize> = area
+            .iter()
+            .filter(|idx| **idx != start_idx)
+            .cloned()
+            .collect();
+        spawner::spawn_region(rng, &area, depth, spawn_table, spawn_list);
+    }
+}
+
+pub fn count_floor_tiles(map: &Map) -> usize {
+    map.tiles
+        .iter()
+        .filter(|tile| **tile == TileType::Floor)
+        .count()
+}
+
+// 外周から2マス以内には出ないようにして、上下左右のどれかに1歩動かす
+pub fn stagger(map: &Map, rng: &mut RandomNumberGenerator, x: &mut i32, y: &mut i32) {
+    match rng.roll_dice(1, 4) {
+        1 => {
+            if *x > 2 {
+                *x -= 1;
+            }
+        }
+        2 => {
+            if *x < map.width as i32 - 2 {
+                *x += 1;
+            }
+        }
+        3 => {
+            if *y > 2 {
+                *y -= 1;
+            }
+        }
+        _ => {
+            if *y < map.height as i32 - 2 {
+                *y += 1;
+            }
+        }
+    }
+}
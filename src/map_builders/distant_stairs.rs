@@ -0,0 +1,131 @@
+use super::*;
+
+// 他のビルダーが作ったマップに、開始位置から一番遠い場所へ下り階段を置く。
+// テンプレートなどですでに階段があるならそれを使い、たどり着けることだけ確かめる。
+// 2階層目以降は開始位置に上り階段を置く
+pub struct DistantStairsBuilder {
+    map: Map,
+    starting_position: Position,
+    spawn_list: Vec<(usize, SpawnType)>,
+    previous_builder: Box<dyn MapBuilder>,
+}
+
+impl MapBuilder for DistantStairsBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, raws, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl DistantStairsBuilder {
+    pub fn new(new_depth: i32, previous_builder: Box<dyn MapBuilder>) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            spawn_list: Vec::new(),
+            previous_builder,
+        }
+    }
+
+    fn build(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.previous_builder.build_map(rng, raws, spawn_table);
+        self.map = self.previous_builder.get_map();
+        self.starting_position = self.previous_builder.get_starting_position();
+        self.spawn_list = self.previous_builder.get_spawn_list().clone();
+
+        if !self.map.tiles.contains(&TileType::DownStairs) {
+            self.place_distant_stairs();
+        }
+        self.connect_unreachable_stairs();
+
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        if self.map.depth > 1 && self.map.tiles[start_idx] != TileType::DownStairs {
+            self.map.tiles[start_idx] = TileType::UpStairs;
+        }
+    }
+
+    // 部屋があれば一番遠い部屋の中心に、なければ一番遠いタイルに置く
+    fn place_distant_stairs(&mut self) {
+        let distances = self.distances_from_start();
+        let stairs_idx = if self.map.rooms.is_empty() {
+            farthest_reachable(&distances, 0..self.map.tiles.len())
+        } else {
+            let map = &self.map;
+            let room_centers = map.rooms.iter().map(|room| {
+                let (x, y) = room.center();
+                map.xy_idx(x, y)
+            });
+            farthest_reachable(&distances, room_centers)
+        };
+
+        if let Some(stairs_idx) = stairs_idx {
+            self.map.tiles[stairs_idx] = TileType::DownStairs;
+        }
+    }
+
+    // 開始位置から歩いて行けない階段があれば、一番近い到達可能なタイルまで通路を掘る
+    fn connect_unreachable_stairs(&mut self) {
+        let distances = self.distances_from_start();
+        let unreachable: Vec<usize> = self
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(idx, tile)| {
+                **tile == TileType::DownStairs && distances[*idx] == dijkstra::UNREACHABLE
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+
+        for stairs_idx in unreachable {
+            let stairs = idx_to_position(&self.map, stairs_idx);
+            let reachable: Vec<usize> = (0..self.map.tiles.len())
+                .filter(|idx| distances[*idx] < dijkstra::UNREACHABLE)
+                .collect();
+            let target = idx_to_position(
+                &self.map,
+                nearest_tile(&self.map, &reachable, Point::new(stairs.x, stairs.y)),
+            );
+
+            apply_horizontal_tunnel(&mut self.map, stairs.x, target.x, stairs.y);
+            apply_vertical_tunnel(&mut self.map, stairs.y, target.y, target.x);
+            self.map.tiles[stairs_idx] = TileType::DownStairs;
+        }
+    }
+
+    fn distances_from_start(&self) -> Vec<f32> {
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        dijkstra_distances(&self.map, start_idx)
+    }
+}
+
+fn farthest_reachable(distances: &[f32], candidates: impl Iterator<Item = usize>) -> Option<usize> {
+    candidates
+        .filter(|idx| distances[*idx] < dijkstra::UNREACHABLE)
+        .max_by(|a, b| distances[*a].partial_cmp(&distances[*b]).unwrap())
+}
@@ -0,0 +1,207 @@
+use super::*;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum DLAAlgorithm {
+    // 外から歩いてきて床にぶつかったところを掘る
+    WalkInwards,
+    // 中心から歩き出して壁にぶつかったところを掘る
+    WalkOutwards,
+    // 外から中心へ直線で向かい、床にぶつかったところを掘る
+    CentralAttractor,
+}
+
+pub struct DLASettings {
+    pub algorithm: DLAAlgorithm,
+    pub brush_size: i32,
+    pub floor_percent: f32,
+}
+
+pub struct DLABuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    spawn_list: Vec<(usize, SpawnType)>,
+    settings: DLASettings,
+}
+
+impl MapBuilder for DLABuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        _raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl DLABuilder {
+    pub fn new(new_depth: i32, settings: DLASettings) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            depth: new_depth,
+            spawn_list: Vec::new(),
+            settings,
+        }
+    }
+
+    pub fn walk_inwards(new_depth: i32) -> Self {
+        Self::new(
+            new_depth,
+            DLASettings {
+                algorithm: DLAAlgorithm::WalkInwards,
+                brush_size: 1,
+                floor_percent: 0.25,
+            },
+        )
+    }
+
+    pub fn walk_outwards(new_depth: i32) -> Self {
+        Self::new(
+            new_depth,
+            DLASettings {
+                algorithm: DLAAlgorithm::WalkOutwards,
+                brush_size: 2,
+                floor_percent: 0.25,
+            },
+        )
+    }
+
+    pub fn central_attractor(new_depth: i32) -> Self {
+        Self::new(
+            new_depth,
+            DLASettings {
+                algorithm: DLAAlgorithm::CentralAttractor,
+                brush_size: 2,
+                floor_percent: 0.25,
+            },
+        )
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator, spawn_table: &RandomTable<SpawnType>) {
+        // 中心に小さな種を掘っておく
+        self.starting_position =
+            Position::new(self.map.width as i32 / 2, self.map.height as i32 / 2);
+        let (start_x, start_y) = (self.starting_position.x, self.starting_position.y);
+        for (x, y) in [
+            (start_x, start_y),
+            (start_x - 1, start_y),
+            (start_x + 1, start_y),
+            (start_x, start_y - 1),
+            (start_x, start_y + 1),
+        ]
+        .iter()
+        {
+            let idx = self.map.xy_idx(*x, *y);
+            self.map.tiles[idx] = TileType::Floor;
+        }
+
+        let total_tiles = self.map.width * self.map.height;
+        let desired_floor_tiles = (self.settings.floor_percent * total_tiles as f32) as usize;
+
+        // 毎回数え直すと遅いので、掘った数を足していく
+        let mut floor_tiles = count_floor_tiles(&self.map);
+        while floor_tiles < desired_floor_tiles {
+            floor_tiles += match self.settings.algorithm {
+                DLAAlgorithm::WalkInwards => self.walk_inwards_step(rng),
+                DLAAlgorithm::WalkOutwards => self.walk_outwards_step(rng),
+                DLAAlgorithm::CentralAttractor => self.central_attractor_step(rng),
+            };
+        }
+
+        let start_idx = self.map.xy_idx(start_x, start_y);
+        let region = connected_region(&self.map, start_idx);
+        keep_only_region(&mut self.map, &region);
+
+        let noise_areas = generate_voronoi_spawn_regions(&self.map, rng);
+        spawn_noise_areas(
+            &self.map,
+            rng,
+            &noise_areas,
+            &self.starting_position,
+            self.depth,
+            spawn_table,
+            &mut self.spawn_list,
+        );
+    }
+
+    fn random_point(&self, rng: &mut RandomNumberGenerator) -> (i32, i32) {
+        (
+            rng.roll_dice(1, self.map.width as i32 - 3) + 1,
+            rng.roll_dice(1, self.map.height as i32 - 3) + 1,
+        )
+    }
+
+    fn walk_inwards_step(&mut self, rng: &mut RandomNumberGenerator) -> usize {
+        let (mut digger_x, mut digger_y) = self.random_point(rng);
+        let (mut prev_x, mut prev_y) = (digger_x, digger_y);
+        while self.map.tiles[self.map.xy_idx(digger_x, digger_y)] == TileType::Wall {
+            prev_x = digger_x;
+            prev_y = digger_y;
+            stagger(&self.map, rng, &mut digger_x, &mut digger_y);
+        }
+        self.paint(prev_x, prev_y)
+    }
+
+    fn walk_outwards_step(&mut self, rng: &mut RandomNumberGenerator) -> usize {
+        let (mut digger_x, mut digger_y) = (self.starting_position.x, self.starting_position.y);
+        while self.map.tiles[self.map.xy_idx(digger_x, digger_y)] != TileType::Wall {
+            stagger(&self.map, rng, &mut digger_x, &mut digger_y);
+        }
+        self.paint(digger_x, digger_y)
+    }
+
+    fn central_attractor_step(&mut self, rng: &mut RandomNumberGenerator) -> usize {
+        let (digger_x, digger_y) = self.random_point(rng);
+        let (mut prev_x, mut prev_y) = (digger_x, digger_y);
+        let path = rltk::line2d(
+            rltk::LineAlg::Bresenham,
+            Point::new(digger_x, digger_y),
+            Point::new(self.starting_position.x, self.starting_position.y),
+        );
+
+        for step in path.iter() {
+            if self.map.tiles[self.map.xy_idx(step.x, step.y)] != TileType::Wall {
+                break;
+            }
+            prev_x = step.x;
+            prev_y = step.y;
+        }
+        self.paint(prev_x, prev_y)
+    }
+
+    // 壁から床に変えたタイルの数を返す
+    fn paint(&mut self, x: i32, y: i32) -> usize {
+        let mut carved = 0;
+        let half_brush_size = self.settings.brush_size / 2;
+        for brush_y in y - half_brush_size..y - half_brush_size + self.settings.brush_size {
+            for brush_x in x - half_brush_size..x - half_brush_size + self.settings.brush_size {
+                if brush_x > 1
+                    && brush_x < self.map.width as i32 - 1
+                    && brush_y > 1
+                    && brush_y < self.map.height as i32 - 1
+                {
+                    let idx = self.map.xy_idx(brush_x, brush_y);
+                    if self.map.tiles[idx] == TileType::Wall {
+                        carved += 1;
+                    }
+                    self.map.tiles[idx] = TileType::Floor;
+                }
+            }
+        }
+        carved
+    }
+}
@@ -0,0 +1,98 @@
+use super::*;
+
+pub const DOOR: &str = "Door";
+
+// 他のビルダーが作った部屋の壁に通路が開いている場所へドアを置く。
+// 部屋を持たないマップには何もしない
+pub struct DoorPlacementBuilder {
+    map: Map,
+    starting_position: Position,
+    spawn_list: Vec<(usize, SpawnType)>,
+    previous_builder: Box<dyn MapBuilder>,
+}
+
+impl MapBuilder for DoorPlacementBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, raws, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl DoorPlacementBuilder {
+    pub fn new(new_depth: i32, previous_builder: Box<dyn MapBuilder>) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            spawn_list: Vec::new(),
+            previous_builder,
+        }
+    }
+
+    fn build(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.previous_builder.build_map(rng, raws, spawn_table);
+        self.map = self.previous_builder.get_map();
+        self.starting_position = self.previous_builder.get_starting_position();
+        self.spawn_list = self.previous_builder.get_spawn_list().clone();
+
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        let mut doors = Vec::new();
+        for room in self.map.rooms.iter() {
+            for x in room.x1 + 1..=room.x2 {
+                for y in [room.y1, room.y2 + 1].iter() {
+                    if self.is_doorway(x, *y, (1, 0)) {
+                        doors.push(self.map.xy_idx(x, *y));
+                    }
+                }
+            }
+            for y in room.y1 + 1..=room.y2 {
+                for x in [room.x1, room.x2 + 1].iter() {
+                    if self.is_doorway(*x, y, (0, 1)) {
+                        doors.push(self.map.xy_idx(*x, y));
+                    }
+                }
+            }
+        }
+
+        for idx in doors {
+            let occupied = idx == start_idx || self.spawn_list.iter().any(|(i, _)| *i == idx);
+            if !occupied {
+                self.spawn_list.push((idx, raws.builder_spawn(DOOR)));
+            }
+        }
+    }
+
+    // 床で、壁に沿った方向の両隣が壁なら通路の出入り口とみなす
+    fn is_doorway(&self, x: i32, y: i32, (dx, dy): (i32, i32)) -> bool {
+        if x < 1 || y < 1 || x >= self.map.width as i32 - 1 || y >= self.map.height as i32 - 1 {
+            return false;
+        }
+
+        let tile = |x: i32, y: i32| self.map.tiles[self.map.xy_idx(x, y)];
+        tile(x, y) == TileType::Floor
+            && tile(x - dx, y - dy) == TileType::Wall
+            && tile(x + dx, y + dy) == TileType::Wall
+    }
+}
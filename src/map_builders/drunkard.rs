@@ -0,0 +1,163 @@
+use super::*;
+
+// 寿命の設定は80x43のマップ向け。酔っ払いが届く距離は歩数の平方根に比例するので、
+// 大きなマップでは面積に合わせて寿命を伸ばす
+const LIFETIME_REFERENCE_TILES: usize = 80 * 43;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum DrunkSpawnMode {
+    // 全員が開始位置から掘り始める
+    StartingPoint,
+    // マップ全体のランダムな位置から掘り始める
+    Random,
+    // マップ中央付近のランダムな位置から掘り始める
+    Central,
+}
+
+pub struct DrunkardSettings {
+    pub spawn_mode: DrunkSpawnMode,
+    pub drunken_lifetime: i32,
+    pub floor_percent: f32,
+}
+
+pub struct DrunkardsWalkBuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    spawn_list: Vec<(usize, SpawnType)>,
+    settings: DrunkardSettings,
+}
+
+impl MapBuilder for DrunkardsWalkBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        _raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl DrunkardsWalkBuilder {
+    pub fn new(new_depth: i32, settings: DrunkardSettings) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            depth: new_depth,
+            spawn_list: Vec::new(),
+            settings,
+        }
+    }
+
+    pub fn single_start(new_depth: i32) -> Self {
+        Self::new(
+            new_depth,
+            DrunkardSettings {
+                spawn_mode: DrunkSpawnMode::StartingPoint,
+                drunken_lifetime: 400,
+                floor_percent: 0.5,
+            },
+        )
+    }
+
+    pub fn random_starts(new_depth: i32) -> Self {
+        Self::new(
+            new_depth,
+            DrunkardSettings {
+                spawn_mode: DrunkSpawnMode::Random,
+                drunken_lifetime: 100,
+                floor_percent: 0.4,
+            },
+        )
+    }
+
+    pub fn central_starts(new_depth: i32) -> Self {
+        Self::new(
+            new_depth,
+            DrunkardSettings {
+                spawn_mode: DrunkSpawnMode::Central,
+                drunken_lifetime: 200,
+                floor_percent: 0.45,
+            },
+        )
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator, spawn_table: &RandomTable<SpawnType>) {
+        self.starting_position =
+            Position::new(self.map.width as i32 / 2, self.map.height as i32 / 2);
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        self.map.tiles[start_idx] = TileType::Floor;
+
+        let total_tiles = self.map.width * self.map.height;
+        let desired_floor_tiles = (self.settings.floor_percent * total_tiles as f32) as usize;
+        let mut floor_tile_count = count_floor_tiles(&self.map);
+        let mut digger_count = 0;
+        let lifetime = self.settings.drunken_lifetime
+            * i32::max(1, (total_tiles / LIFETIME_REFERENCE_TILES) as i32);
+
+        while floor_tile_count < desired_floor_tiles {
+            let (mut drunk_x, mut drunk_y) = if digger_count == 0 {
+                (self.starting_position.x, self.starting_position.y)
+            } else {
+                self.digger_start(rng)
+            };
+
+            // 大きなマップで毎回数え直すと遅いので、掘った分だけ数える
+            for _ in 0..lifetime {
+                let drunk_idx = self.map.xy_idx(drunk_x, drunk_y);
+                if self.map.tiles[drunk_idx] == TileType::Wall {
+                    self.map.tiles[drunk_idx] = TileType::Floor;
+                    floor_tile_count += 1;
+                }
+                stagger(&self.map, rng, &mut drunk_x, &mut drunk_y);
+            }
+
+            digger_count += 1;
+        }
+
+        let region = connected_region(&self.map, start_idx);
+        keep_only_region(&mut self.map, &region);
+
+        let noise_areas = generate_voronoi_spawn_regions(&self.map, rng);
+        spawn_noise_areas(
+            &self.map,
+            rng,
+            &noise_areas,
+            &self.starting_position,
+            self.depth,
+            spawn_table,
+            &mut self.spawn_list,
+        );
+    }
+
+    fn digger_start(&self, rng: &mut RandomNumberGenerator) -> (i32, i32) {
+        let width = self.map.width as i32;
+        let height = self.map.height as i32;
+        match self.settings.spawn_mode {
+            DrunkSpawnMode::StartingPoint => (self.starting_position.x, self.starting_position.y),
+            DrunkSpawnMode::Random => (
+                rng.roll_dice(1, width - 3) + 1,
+                rng.roll_dice(1, height - 3) + 1,
+            ),
+            DrunkSpawnMode::Central => (
+                width / 4 + rng.roll_dice(1, width / 2),
+                height / 4 + rng.roll_dice(1, height / 2),
+            ),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use super::*;
+
+pub struct MazeBuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    spawn_list: Vec<(usize, SpawnType)>,
+    loop_percent: i32,
+    columns: i32,
+    rows: i32,
+}
+
+impl MapBuilder for MazeBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        _raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl MazeBuilder {
+    // loop_percentが0なら完全迷路、大きくするほど壁を抜いて回り道を増やす
+    pub fn new(new_depth: i32, loop_percent: i32) -> Self {
+        let map = Map::new(new_depth, MAPWIDTH, MAPHEIGHT);
+        let columns = (map.width as i32 - 1) / 2;
+        let rows = (map.height as i32 - 1) / 2;
+        Self {
+            map,
+            starting_position: Position::new(0, 0),
+            depth: new_depth,
+            spawn_list: Vec::new(),
+            loop_percent,
+            columns,
+            rows,
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator, spawn_table: &RandomTable<SpawnType>) {
+        self.carve_maze(rng);
+        if self.loop_percent > 0 {
+            self.inject_loops(rng);
+        }
+
+        let (start_x, start_y) = self.cell_to_tile(0);
+        self.starting_position = Position::new(start_x, start_y);
+
+        let noise_areas = generate_voronoi_spawn_regions(&self.map, rng);
+        spawn_noise_areas(
+            &self.map,
+            rng,
+            &noise_areas,
+            &self.starting_position,
+            self.depth,
+            spawn_table,
+            &mut self.spawn_list,
+        );
+    }
+
+    // 再帰的バックトラッキングを、スタックを使って繰り返しで行う
+    fn carve_maze(&mut self, rng: &mut RandomNumberGenerator) {
+        let mut visited = vec![false; (self.columns * self.rows) as usize];
+        let mut stack: Vec<i32> = vec![0];
+        visited[0] = true;
+        self.carve_cell(0);
+
+        while let Some(current) = stack.last().cloned() {
+            let neighbours: Vec<i32> = self
+                .neighbour_cells(current)
+                .into_iter()
+                .filter(|cell| !visited[*cell as usize])
+                .collect();
+
+            if neighbours.is_empty() {
+                stack.pop();
+                continue;
+            }
+
+            let next = neighbours[(rng.roll_dice(1, neighbours.len() as i32) - 1) as usize];
+            let (current_x, current_y) = self.cell_to_tile(current);
+            let (next_x, next_y) = self.cell_to_tile(next);
+            let wall_idx = self
+                .map
+                .xy_idx((current_x + next_x) / 2, (current_y + next_y) / 2);
+            self.map.tiles[wall_idx] = TileType::Floor;
+            self.carve_cell(next);
+
+            visited[next as usize] = true;
+            stack.push(next);
+        }
+    }
+
+    // 両側が床になっている壁を一定の確率で抜く
+    fn inject_loops(&mut self, rng: &mut RandomNumberGenerator) {
+        for y in 1..self.map.height as i32 - 1 {
+            for x in 1..self.map.width as i32 - 1 {
+                let idx = self.map.xy_idx(x, y);
+                if self.map.tiles[idx] != TileType::Wall || (x % 2 == 1) == (y % 2 == 1) {
+                    continue;
+                }
+
+                let is_floor =
+                    |tx: i32, ty: i32| self.map.tiles[self.map.xy_idx(tx, ty)] != TileType::Wall;
+                let between_floors = (is_floor(x - 1, y) && is_floor(x + 1, y))
+                    || (is_floor(x, y - 1) && is_floor(x, y + 1));
+
+                if between_floors && rng.roll_dice(1, 100) <= self.loop_percent {
+                    self.map.tiles[idx] = TileType::Floor;
+                }
+            }
+        }
+    }
+
+    fn neighbour_cells(&self, cell: i32) -> Vec<i32> {
+        let x = cell % self.columns;
+        let y = cell / self.columns;
+        let mut neighbours = Vec::new();
+        if x > 0 {
+            neighbours.push(cell - 1);
+        }
+        if x < self.columns - 1 {
+            neighbours.push(cell + 1);
+        }
+        if y > 0 {
+            neighbours.push(cell - self.columns);
+        }
+        if y < self.rows - 1 {
+            neighbours.push(cell + self.columns);
+        }
+        neighbours
+    }
+
+    fn carve_cell(&mut self, cell: i32) {
+        let (x, y) = self.cell_to_tile(cell);
+        let idx = self.map.xy_idx(x, y);
+        self.map.tiles[idx] = TileType::Floor;
+    }
+
+    fn cell_to_tile(&self, cell: i32) -> (i32, i32) {
+        (1 + (cell % self.columns) * 2, 1 + (cell / self.columns) * 2)
+    }
+}
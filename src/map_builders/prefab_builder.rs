@@ -0,0 +1,260 @@
+use super::*;
+mod prefab_levels;
+use prefab_levels::PrefabLevel;
+pub use prefab_levels::{level_for_depth, ORC_FORTRESS};
+mod prefab_rooms;
+use prefab_rooms::PrefabRoom;
+use std::collections::HashSet;
+
+// 大きなマップでは候補が多すぎるので、試す場所の数に上限を設ける
+const MAX_VAULT_ATTEMPTS: usize = 50;
+
+// テンプレートの文字と、その場所に置くもの
+const GLYPH_SPAWNS: &[(char, &str)] = &[
+    ('g', "Goblin"),
+    ('o', "Orc"),
+    ('!', "Health Potion"),
+    (')', "Magic Missile Scroll"),
+    ('/', "Dagger"),
+    ('(', "Shield"),
+];
+
+pub fn spawn_names() -> impl Iterator<Item = &'static str> {
+    GLYPH_SPAWNS.iter().map(|(_, name)| *name)
+}
+
+pub enum PrefabMode {
+    // レベル全体をテンプレートから作る
+    Constant { level: PrefabLevel },
+    // 別のビルダーで作ったマップに小部屋を埋め込む
+    RoomVaults,
+}
+
+pub struct PrefabBuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    spawn_list: Vec<(usize, SpawnType)>,
+    mode: PrefabMode,
+    previous_builder: Option<Box<dyn MapBuilder>>,
+}
+
+impl MapBuilder for PrefabBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, raws, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl PrefabBuilder {
+    pub fn constant(new_depth: i32, level: PrefabLevel) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            depth: new_depth,
+            spawn_list: Vec::new(),
+            mode: PrefabMode::Constant { level },
+            previous_builder: None,
+        }
+    }
+
+    pub fn room_vaults(new_depth: i32, previous_builder: Box<dyn MapBuilder>) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            depth: new_depth,
+            spawn_list: Vec::new(),
+            mode: PrefabMode::RoomVaults,
+            previous_builder: Some(previous_builder),
+        }
+    }
+
+    fn build(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        match self.mode {
+            PrefabMode::Constant { level } => self.load_level(raws, level),
+            PrefabMode::RoomVaults => self.apply_room_vaults(rng, raws, spawn_table),
+        }
+    }
+
+    fn load_level(&mut self, raws: &RawMaster, level: PrefabLevel) {
+        let template = read_template(level.template, level.width, level.height);
+        let offset_x = (self.map.width - level.width) as i32 / 2;
+        let offset_y = (self.map.height - level.height) as i32 / 2;
+
+        for ty in 0..level.height {
+            for tx in 0..level.width {
+                let idx = self.map.xy_idx(tx as i32 + offset_x, ty as i32 + offset_y);
+                self.char_to_map(raws, template[ty * level.width + tx], idx);
+            }
+        }
+    }
+
+    fn apply_room_vaults(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        if let Some(previous_builder) = self.previous_builder.as_mut() {
+            previous_builder.build_map(rng, raws, spawn_table);
+            self.map = previous_builder.get_map();
+            self.starting_position = previous_builder.get_starting_position();
+            self.spawn_list = previous_builder.get_spawn_list().clone();
+        }
+
+        let mut possible_vaults: Vec<PrefabRoom> = prefab_rooms::ROOM_VAULTS
+            .iter()
+            .filter(|vault| self.depth >= vault.first_depth && self.depth <= vault.last_depth)
+            .cloned()
+            .collect();
+        if possible_vaults.is_empty() {
+            return;
+        }
+
+        let n_vaults = i32::min(rng.roll_dice(1, 3), possible_vaults.len() as i32);
+        let mut used_tiles: HashSet<usize> = HashSet::new();
+
+        for _ in 0..n_vaults {
+            let vault_index = (rng.roll_dice(1, possible_vaults.len() as i32) - 1) as usize;
+            let vault = possible_vaults.remove(vault_index);
+            let template = read_template(vault.template, vault.width, vault.height);
+
+            let mut candidates = self.vault_candidates(&vault, &used_tiles);
+            let mut attempts = 0;
+            while !candidates.is_empty() && attempts < MAX_VAULT_ATTEMPTS {
+                attempts += 1;
+                let pick = (rng.roll_dice(1, candidates.len() as i32) - 1) as usize;
+                let (x, y) = candidates.remove(pick);
+                if self.try_stamp_vault(raws, &vault, &template, x, y, &mut used_tiles) {
+                    break;
+                }
+            }
+        }
+    }
+
+    // 下のタイルがすべて壁、またはすべて床で、他の小部屋や開始位置と重ならない場所を集める
+    fn vault_candidates(&self, vault: &PrefabRoom, used_tiles: &HashSet<usize>) -> Vec<(i32, i32)> {
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        let mut candidates = Vec::new();
+
+        for y in 1..(self.map.height - vault.height) as i32 {
+            for x in 1..(self.map.width - vault.width) as i32 {
+                let footprint = vault_footprint(&self.map, vault, x, y);
+                let first_tile = self.map.tiles[footprint[0]];
+                let possible = (first_tile == TileType::Wall || first_tile == TileType::Floor)
+                    && footprint.iter().all(|idx| {
+                        self.map.tiles[*idx] == first_tile
+                            && !used_tiles.contains(idx)
+                            && *idx != start_idx
+                    });
+
+                if possible {
+                    candidates.push((x, y));
+                }
+            }
+        }
+
+        candidates
+    }
+
+    // 埋め込んだ後もすべての床に開始位置から歩いて行ける場合だけ採用する
+    fn try_stamp_vault(
+        &mut self,
+        raws: &RawMaster,
+        vault: &PrefabRoom,
+        template: &[char],
+        x: i32,
+        y: i32,
+        used_tiles: &mut HashSet<usize>,
+    ) -> bool {
+        let original_map = self.map.clone();
+        let original_spawn_list = self.spawn_list.clone();
+        let footprint = vault_footprint(&self.map, vault, x, y);
+
+        self.spawn_list.retain(|(idx, _)| !footprint.contains(idx));
+        for ty in 0..vault.height {
+            for tx in 0..vault.width {
+                let idx = self.map.xy_idx(x + tx as i32, y + ty as i32);
+                self.char_to_map(raws, template[ty * vault.width + tx], idx);
+            }
+        }
+
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        if !is_fully_connected(&self.map, start_idx) {
+            self.map = original_map;
+            self.spawn_list = original_spawn_list;
+            return false;
+        }
+
+        used_tiles.extend(footprint);
+        true
+    }
+
+    fn char_to_map(&mut self, raws: &RawMaster, ch: char, idx: usize) {
+        match ch {
+            ' ' => {}
+            '#' => self.map.tiles[idx] = TileType::Wall,
+            '.' => self.map.tiles[idx] = TileType::Floor,
+            '>' => self.map.tiles[idx] = TileType::DownStairs,
+            '@' => {
+                self.map.tiles[idx] = TileType::Floor;
+                self.starting_position = idx_to_position(&self.map, idx);
+            }
+            _ => {
+                let name = GLYPH_SPAWNS
+                    .iter()
+                    .find(|(glyph, _)| *glyph == ch)
+                    .map(|(_, name)| *name)
+                    .unwrap_or_else(|| panic!("Unknown glyph in prefab: {}", ch));
+                self.map.tiles[idx] = TileType::Floor;
+                self.spawn_list.push((idx, raws.builder_spawn(name)));
+            }
+        }
+    }
+}
+
+// 先頭の改行を読み飛ばし、各行を幅に合わせて空白で埋める
+fn read_template(template: &str, width: usize, height: usize) -> Vec<char> {
+    let mut tiles = vec![' '; width * height];
+    for (y, line) in template.lines().skip(1).take(height).enumerate() {
+        for (x, ch) in line.chars().take(width).enumerate() {
+            tiles[y * width + x] = ch;
+        }
+    }
+    tiles
+}
+
+fn vault_footprint(map: &Map, vault: &PrefabRoom, x: i32, y: i32) -> Vec<usize> {
+    let mut footprint = Vec::new();
+    for ty in 0..vault.height as i32 {
+        for tx in 0..vault.width as i32 {
+            footprint.push(map.xy_idx(x + tx, y + ty));
+        }
+    }
+    footprint
+}
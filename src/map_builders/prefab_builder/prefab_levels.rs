@@ -0,0 +1,44 @@
+// 丸ごと手で書いたレベル。マップより小さい場合は中央に置き、残りは壁になる
+#[derive(PartialEq, Copy, Clone)]
+pub struct PrefabLevel {
+    pub template: &'static str,
+    pub width: usize,
+    pub height: usize,
+    pub depth: i32,
+}
+
+pub fn level_for_depth(depth: i32) -> Option<PrefabLevel> {
+    [ORC_FORTRESS]
+        .iter()
+        .find(|level| level.depth == depth)
+        .cloned()
+}
+
+pub const ORC_FORTRESS: PrefabLevel = PrefabLevel {
+    template: ORC_FORTRESS_MAP,
+    width: 48,
+    height: 19,
+    depth: 3,
+};
+
+const ORC_FORTRESS_MAP: &str = "
+################################################
+#@.....#...........................#...........#
+#......#..........g................#...........#
+#......#...........................#.....g.....#
+#..................................#...........#
+#......#.......##########.##########...........#
+#......#.......#........#.#...........#........#
+####.###.......#..o..!..#.#...........#........#
+#..............#........#.............#........#
+#..............####.#####.#...........#....>...#
+#.........g.......................o............#
+#..............####.#####.#...........#........#
+#..............#........#.#...........#........#
+####.###.......#..(..o..#.#...........#........#
+#......#.......#........#.#...........#........#
+#......#.......##########.##########...........#
+#......#.................................g.....#
+#......#......................................)#
+################################################
+";
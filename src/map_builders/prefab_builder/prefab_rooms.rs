@@ -0,0 +1,59 @@
+// 既存のマップに埋め込む小部屋。空白の部分は元のタイルをそのまま残す
+#[derive(PartialEq, Copy, Clone)]
+pub struct PrefabRoom {
+    pub template: &'static str,
+    pub width: usize,
+    pub height: usize,
+    pub first_depth: i32,
+    pub last_depth: i32,
+}
+
+pub const ROOM_VAULTS: [PrefabRoom; 3] = [HEALING_SHRINE, TREASURE_VAULT, AMBUSH_ROOM];
+
+pub const HEALING_SHRINE: PrefabRoom = PrefabRoom {
+    template: HEALING_SHRINE_MAP,
+    width: 5,
+    height: 5,
+    first_depth: 1,
+    last_depth: 100,
+};
+
+const HEALING_SHRINE_MAP: &str = "
+ ... 
+.#.#.
+..!..
+.#.#.
+ ... 
+";
+
+pub const TREASURE_VAULT: PrefabRoom = PrefabRoom {
+    template: TREASURE_VAULT_MAP,
+    width: 7,
+    height: 5,
+    first_depth: 2,
+    last_depth: 100,
+};
+
+const TREASURE_VAULT_MAP: &str = "
+#######
+#..!..#
+#.)/(.#
+##...##
+###.###
+";
+
+pub const AMBUSH_ROOM: PrefabRoom = PrefabRoom {
+    template: AMBUSH_ROOM_MAP,
+    width: 7,
+    height: 5,
+    first_depth: 3,
+    last_depth: 100,
+};
+
+const AMBUSH_ROOM_MAP: &str = "
+#.....#
+.o...g.
+...!...
+.g...o.
+#.....#
+";
@@ -0,0 +1,111 @@
+use super::*;
+
+// この深さから川は溶岩になる
+const LAVA_DEPTH: i32 = 6;
+// 川の中心の渡れない部分の半分の幅
+const CORE_HALF_WIDTH: i32 = 1;
+// 岸の幅
+const BANK_WIDTH: i32 = 1;
+// だいたいこの行数ごとに橋を架ける
+const BRIDGE_INTERVAL: i32 = 15;
+
+// 他のビルダーが作ったマップを南北に横切る川を流す。
+// 浅い階層では深い水と浅瀬、深い階層では溶岩と砂利の岸になり、ところどころに橋を架ける
+pub struct RiverBuilder {
+    map: Map,
+    starting_position: Position,
+    spawn_list: Vec<(usize, SpawnType)>,
+    previous_builder: Box<dyn MapBuilder>,
+}
+
+impl MapBuilder for RiverBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, raws, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl RiverBuilder {
+    pub fn new(new_depth: i32, previous_builder: Box<dyn MapBuilder>) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            spawn_list: Vec::new(),
+            previous_builder,
+        }
+    }
+
+    fn build(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.previous_builder.build_map(rng, raws, spawn_table);
+        self.map = self.previous_builder.get_map();
+        self.starting_position = self.previous_builder.get_starting_position();
+        self.spawn_list = self.previous_builder.get_spawn_list().clone();
+
+        let (core, bank) = if self.map.depth >= LAVA_DEPTH {
+            (TileType::Lava, TileType::Gravel)
+        } else {
+            (TileType::DeepWater, TileType::ShallowWater)
+        };
+
+        let width = self.map.width as i32;
+        let height = self.map.height as i32;
+        let margin = CORE_HALF_WIDTH + BANK_WIDTH + 1;
+        let mut river_x = rng.range(width / 4, width * 3 / 4);
+        let mut next_bridge = rng.range(1, BRIDGE_INTERVAL);
+        for y in 1..height - 1 {
+            river_x = i32::min(
+                width - margin - 1,
+                i32::max(margin, river_x + rng.range(-1, 2)),
+            );
+
+            let is_bridge = y == next_bridge;
+            if is_bridge {
+                next_bridge += rng.range(BRIDGE_INTERVAL / 2, BRIDGE_INTERVAL * 3 / 2);
+            }
+
+            for dx in -(CORE_HALF_WIDTH + BANK_WIDTH)..=CORE_HALF_WIDTH + BANK_WIDTH {
+                let idx = self.map.xy_idx(river_x + dx, y);
+                self.map.tiles[idx] = if dx.abs() > CORE_HALF_WIDTH {
+                    bank
+                } else if is_bridge {
+                    TileType::Bridge
+                } else {
+                    core
+                };
+            }
+        }
+
+        // 開始位置が川の中になったら足場を残す
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        if self.map.tiles[start_idx] == core {
+            self.map.tiles[start_idx] = TileType::Bridge;
+        }
+
+        let map = &self.map;
+        self.spawn_list
+            .retain(|(idx, _)| map.tiles[*idx].is_walkable() && !map.tiles[*idx].is_hazardous());
+    }
+}
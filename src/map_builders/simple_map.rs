@@ -0,0 +1,96 @@
+use super::*;
+
+pub struct SimpleMapBuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    spawn_list: Vec<(usize, SpawnType)>,
+}
+
+impl MapBuilder for SimpleMapBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        _raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.rooms_and_corridors(rng, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl SimpleMapBuilder {
+    pub fn new(new_depth: i32) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            depth: new_depth,
+            spawn_list: Vec::new(),
+        }
+    }
+
+    fn rooms_and_corridors(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        const MAX_ROOMS: i32 = 30;
+        const MIN_SIZE: i32 = 6;
+        const MAX_SIZE: i32 = 10;
+        for _ in 0..MAX_ROOMS {
+            let w = rng.range(MIN_SIZE, MAX_SIZE) as usize;
+            let h = rng.range(MIN_SIZE, MAX_SIZE) as usize;
+            let x = rng.roll_dice(1, (self.map.width - w - 1) as i32) - 1;
+            let y = rng.roll_dice(1, (self.map.height - h - 1) as i32) - 1;
+            let new_room = Rect::new(x, y, w as i32, h as i32);
+            let mut ok = true;
+            for other_room in self.map.rooms.iter() {
+                if new_room.intersect(other_room) {
+                    ok = false
+                }
+            }
+            if ok {
+                apply_room_to_map(&mut self.map, &new_room);
+
+                if !self.map.rooms.is_empty() {
+                    let (new_x, new_y) = new_room.center();
+                    let (prev_x, prev_y) = self.map.rooms[self.map.rooms.len() - 1].center();
+                    if rng.range(0, 2) == 1 {
+                        apply_horizontal_tunnel(&mut self.map, prev_x, new_x, prev_y);
+                        apply_vertical_tunnel(&mut self.map, prev_y, new_y, new_x);
+                    } else {
+                        apply_vertical_tunnel(&mut self.map, prev_y, new_y, prev_x);
+                        apply_horizontal_tunnel(&mut self.map, prev_x, new_x, new_y);
+                    }
+                }
+
+                self.map.rooms.push(new_room);
+            }
+        }
+
+        let (start_x, start_y) = self.map.rooms[0].center();
+        self.starting_position = Position::new(start_x, start_y);
+
+        for room in self.map.rooms.iter().skip(1) {
+            spawner::spawn_room(
+                &self.map,
+                rng,
+                room,
+                self.depth,
+                spawn_table,
+                &mut self.spawn_list,
+            );
+        }
+    }
+}
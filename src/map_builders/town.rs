@@ -0,0 +1,240 @@
+use super::*;
+
+const MAX_BUILDINGS: i32 = 40;
+const MIN_SIZE: i32 = 5;
+const MAX_SIZE: i32 = 14;
+// 建物と建物の間に空ける道幅
+const STREET_WIDTH: i32 = 2;
+// 町の西端から出発し、東端の階段からダンジョンへ降りる
+const EDGE_MARGIN: i32 = 6;
+
+const BARKEEP: &str = "Barkeep";
+const PATRON: &str = "Patron";
+const PRIEST: &str = "Priest";
+const BLACKSMITH: &str = "Blacksmith";
+const TOWNSPERSON: &str = "Townsperson";
+// 町に置く住人
+pub const SPAWN_NAMES: &[&str] = &[BARKEEP, PATRON, PRIEST, BLACKSMITH, TOWNSPERSON];
+
+#[derive(PartialEq, Copy, Clone)]
+enum BuildingType {
+    Tavern,
+    Temple,
+    Blacksmith,
+    House,
+}
+
+// 1階層目の町。広場に建物を並べ、それぞれに住人を置く。
+// 建物はmap.roomsに入れるので、入口にはDoorPlacementBuilderがドアを置く
+pub struct TownBuilder {
+    map: Map,
+    starting_position: Position,
+    spawn_list: Vec<(usize, SpawnType)>,
+}
+
+impl MapBuilder for TownBuilder {
+    // 町の住人は建物ごとに決まっているので出現表は使わない
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        _spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, raws);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl TownBuilder {
+    pub fn new(new_depth: i32) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            spawn_list: Vec::new(),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator, raws: &RawMaster) {
+        let width = self.map.width as i32;
+        let height = self.map.height as i32;
+
+        // 外周だけ壁にして、残りは草地にする
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let idx = self.map.xy_idx(x, y);
+                self.map.tiles[idx] = TileType::Grass;
+            }
+        }
+
+        self.starting_position = Position::new(1, height / 2);
+        let stairs_y = rng.range(1, height - 1);
+        self.lay_road(stairs_y);
+        let stairs_idx = self.map.xy_idx(width - 2, stairs_y);
+        self.map.tiles[stairs_idx] = TileType::DownStairs;
+
+        self.place_buildings(rng);
+
+        // 大きい建物から順に酒場、神殿、鍛冶屋にする
+        let mut buildings = self.map.rooms.clone();
+        buildings.sort_by_key(|b| -((b.x2 - b.x1) * (b.y2 - b.y1)));
+        for (i, building) in buildings.iter().enumerate() {
+            let building_type = match i {
+                0 => BuildingType::Tavern,
+                1 => BuildingType::Temple,
+                2 => BuildingType::Blacksmith,
+                _ => BuildingType::House,
+            };
+            self.populate(rng, raws, building, building_type);
+        }
+
+        self.spawn_townsfolk(rng, raws);
+    }
+
+    // 町の入口から東端を通って階段まで砂利道を通す
+    fn lay_road(&mut self, stairs_y: i32) {
+        let width = self.map.width as i32;
+        let road_y = self.starting_position.y;
+        for x in 1..width - 1 {
+            let idx = self.map.xy_idx(x, road_y);
+            self.map.tiles[idx] = TileType::Gravel;
+        }
+        for y in i32::min(road_y, stairs_y)..=i32::max(road_y, stairs_y) {
+            let idx = self.map.xy_idx(width - 2, y);
+            self.map.tiles[idx] = TileType::Gravel;
+        }
+    }
+
+    fn place_buildings(&mut self, rng: &mut RandomNumberGenerator) {
+        let width = self.map.width as i32;
+        let height = self.map.height as i32;
+        for _ in 0..MAX_BUILDINGS {
+            let w = rng.range(MIN_SIZE, MAX_SIZE);
+            let h = rng.range(MIN_SIZE, MAX_SIZE);
+            let x = rng.range(EDGE_MARGIN, width - EDGE_MARGIN - w - 1);
+            let y = rng.range(STREET_WIDTH + 1, height - STREET_WIDTH - h - 2);
+            let building = Rect::new(x, y, w, h);
+            let with_street = Rect::new(
+                x - STREET_WIDTH,
+                y - STREET_WIDTH,
+                w + STREET_WIDTH * 2,
+                h + STREET_WIDTH * 2,
+            );
+            let on_road = with_street.y1 <= self.starting_position.y
+                && with_street.y2 + 1 >= self.starting_position.y;
+            if on_road
+                || self
+                    .map
+                    .rooms
+                    .iter()
+                    .any(|other| with_street.intersect(other))
+            {
+                continue;
+            }
+
+            self.apply_building(rng, &building);
+            self.map.rooms.push(building);
+        }
+    }
+
+    // 外周を壁で囲み、どこか一辺の中央に入口を開ける
+    fn apply_building(&mut self, rng: &mut RandomNumberGenerator, building: &Rect) {
+        for y in building.y1..=building.y2 + 1 {
+            for x in building.x1..=building.x2 + 1 {
+                let idx = self.map.xy_idx(x, y);
+                self.map.tiles[idx] = TileType::Wall;
+            }
+        }
+        apply_room_to_map(&mut self.map, building);
+
+        let (center_x, center_y) = building.center();
+        let (door_x, door_y) = match rng.roll_dice(1, 4) {
+            1 => (center_x, building.y1),
+            2 => (center_x, building.y2 + 1),
+            3 => (building.x1, center_y),
+            _ => (building.x2 + 1, center_y),
+        };
+        let idx = self.map.xy_idx(door_x, door_y);
+        self.map.tiles[idx] = TileType::Floor;
+    }
+
+    fn populate(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        building: &Rect,
+        building_type: BuildingType,
+    ) {
+        let mut area = Vec::new();
+        for y in building.y1 + 1..=building.y2 {
+            for x in building.x1 + 1..=building.x2 {
+                area.push(self.map.xy_idx(x, y));
+            }
+        }
+
+        let residents: Vec<&str> = match building_type {
+            BuildingType::Tavern => vec![BARKEEP, PATRON, PATRON, PATRON],
+            BuildingType::Temple => vec![PRIEST, PATRON],
+            BuildingType::Blacksmith => vec![BLACKSMITH],
+            BuildingType::House => {
+                if rng.roll_dice(1, 3) == 1 {
+                    vec![]
+                } else {
+                    vec![TOWNSPERSON]
+                }
+            }
+        };
+        for name in residents {
+            self.spawn_in(rng, &mut area, raws.builder_spawn(name));
+        }
+    }
+
+    // 建物の外を歩いている住人
+    fn spawn_townsfolk(&mut self, rng: &mut RandomNumberGenerator, raws: &RawMaster) {
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        let map = &self.map;
+        let mut area: Vec<usize> = (0..map.tiles.len())
+            .filter(|idx| {
+                *idx != start_idx
+                    && map.tiles[*idx] == TileType::Grass
+                    && !map.rooms.iter().any(|building| {
+                        // 入口の前もふさがないように建物の一回り外まで避ける
+                        let pos = idx_to_position(map, *idx);
+                        pos.x >= building.x1 - 1
+                            && pos.x <= building.x2 + 2
+                            && pos.y >= building.y1 - 1
+                            && pos.y <= building.y2 + 2
+                    })
+            })
+            .collect();
+
+        for _ in 0..rng.roll_dice(2, 6) {
+            self.spawn_in(rng, &mut area, raws.builder_spawn(TOWNSPERSON));
+        }
+    }
+
+    fn spawn_in(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        area: &mut Vec<usize>,
+        spawn_type: SpawnType,
+    ) {
+        if area.is_empty() {
+            return;
+        }
+        let i = (rng.roll_dice(1, area.len() as i32) - 1) as usize;
+        self.spawn_list.push((area.remove(i), spawn_type));
+    }
+}
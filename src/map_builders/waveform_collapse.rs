@@ -0,0 +1,125 @@
+use super::*;
+mod constraints;
+use constraints::*;
+mod solver;
+use solver::Solver;
+
+const CHUNK_SIZE: usize = 8;
+const MAX_ATTEMPTS: i32 = 20;
+
+// 見本マップのタイルの隣接関係を学習して、新しいマップを組み立てる
+pub struct WaveformCollapseBuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    spawn_list: Vec<(usize, SpawnType)>,
+    chunk_size: usize,
+    sample_builder: Box<dyn MapBuilder>,
+}
+
+impl MapBuilder for WaveformCollapseBuilder {
+    fn build_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.build(rng, raws, spawn_table);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &Vec<(usize, SpawnType)> {
+        &self.spawn_list
+    }
+}
+
+impl WaveformCollapseBuilder {
+    pub fn derived_map(new_depth: i32, sample_builder: Box<dyn MapBuilder>) -> Self {
+        Self {
+            map: Map::new(new_depth, MAPWIDTH, MAPHEIGHT),
+            starting_position: Position::new(0, 0),
+            depth: new_depth,
+            spawn_list: Vec::new(),
+            chunk_size: CHUNK_SIZE,
+            sample_builder,
+        }
+    }
+
+    fn build(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        raws: &RawMaster,
+        spawn_table: &RandomTable<SpawnType>,
+    ) {
+        self.sample_builder.build_map(rng, raws, spawn_table);
+        let mut sample = self.sample_builder.get_map();
+        for tile in sample.tiles.iter_mut() {
+            if *tile == TileType::DownStairs {
+                *tile = TileType::Floor;
+            }
+        }
+
+        let patterns = build_patterns(&sample, self.chunk_size);
+        let mut constraints = patterns_to_constraints(patterns, self.chunk_size);
+
+        // 矛盾したらやり直し、何度やっても駄目なら見本をそのまま使う
+        let mut solved = false;
+        for _ in 0..MAX_ATTEMPTS {
+            self.map = Map::new(self.depth, MAPWIDTH, MAPHEIGHT);
+            let mut solver = Solver::new(constraints, self.chunk_size, &self.map);
+            while !solver.iteration(&mut self.map, rng) {}
+            solved = solver.possible;
+            constraints = solver.into_constraints();
+            if solved {
+                break;
+            }
+        }
+        if !solved {
+            self.map = sample;
+        }
+
+        self.wall_off_edges();
+
+        let region = largest_region(&self.map);
+        keep_only_region(&mut self.map, &region);
+
+        let center = Point::new(self.map.width / 2, self.map.height / 2);
+        let start_idx = nearest_tile(&self.map, &region, center);
+        self.starting_position = idx_to_position(&self.map, start_idx);
+
+        let noise_areas = generate_voronoi_spawn_regions(&self.map, rng);
+        spawn_noise_areas(
+            &self.map,
+            rng,
+            &noise_areas,
+            &self.starting_position,
+            self.depth,
+            spawn_table,
+            &mut self.spawn_list,
+        );
+    }
+
+    fn wall_off_edges(&mut self) {
+        let width = self.map.width as i32;
+        let height = self.map.height as i32;
+        for x in 0..width {
+            for y in [0, height - 1].iter() {
+                let idx = self.map.xy_idx(x, *y);
+                self.map.tiles[idx] = TileType::Wall;
+            }
+        }
+        for y in 0..height {
+            for x in [0, width - 1].iter() {
+                let idx = self.map.xy_idx(*x, y);
+                self.map.tiles[idx] = TileType::Wall;
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use super::*;
+use std::collections::HashSet;
+
+pub const NORTH: usize = 0;
+pub const SOUTH: usize = 1;
+pub const WEST: usize = 2;
+pub const EAST: usize = 3;
+
+// 見本マップを切り出したチャンクと、各方向に隣接できるチャンクの一覧
+pub struct MapChunk {
+    pub pattern: Vec<TileType>,
+    pub exits: [Vec<bool>; 4],
+    pub has_exits: bool,
+    pub compatible_with: [Vec<usize>; 4],
+}
+
+// 見本マップをchunk_size四方に切り出し、反転したものも含めて重複を除く
+pub fn build_patterns(map: &Map, chunk_size: usize) -> Vec<Vec<TileType>> {
+    let chunks_x = map.width / chunk_size;
+    let chunks_y = map.height / chunk_size;
+    let mut patterns: Vec<Vec<TileType>> = Vec::new();
+    let mut seen: HashSet<Vec<TileType>> = HashSet::new();
+
+    for cy in 0..chunks_y {
+        for cx in 0..chunks_x {
+            for (flip_x, flip_y) in
+                [(false, false), (true, false), (false, true), (true, true)].iter()
+            {
+                let mut pattern = Vec::with_capacity(chunk_size * chunk_size);
+                for y in 0..chunk_size {
+                    for x in 0..chunk_size {
+                        let sx = if *flip_x { chunk_size - 1 - x } else { x };
+                        let sy = if *flip_y { chunk_size - 1 - y } else { y };
+                        let idx = map
+                            .xy_idx((cx * chunk_size + sx) as i32, (cy * chunk_size + sy) as i32);
+                        pattern.push(map.tiles[idx]);
+                    }
+                }
+
+                if seen.insert(pattern.clone()) {
+                    patterns.push(pattern);
+                }
+            }
+        }
+    }
+
+    patterns
+}
+
+pub fn patterns_to_constraints(patterns: Vec<Vec<TileType>>, chunk_size: usize) -> Vec<MapChunk> {
+    let mut constraints: Vec<MapChunk> = patterns
+        .into_iter()
+        .map(|pattern| {
+            let mut exits = [
+                vec![false; chunk_size],
+                vec![false; chunk_size],
+                vec![false; chunk_size],
+                vec![false; chunk_size],
+            ];
+            for i in 0..chunk_size {
+                exits[NORTH][i] = pattern[i] != TileType::Wall;
+                exits[SOUTH][i] = pattern[(chunk_size - 1) * chunk_size + i] != TileType::Wall;
+                exits[WEST][i] = pattern[i * chunk_size] != TileType::Wall;
+                exits[EAST][i] = pattern[i * chunk_size + chunk_size - 1] != TileType::Wall;
+            }
+            let has_exits = exits.iter().any(|side| side.iter().any(|exit| *exit));
+
+            MapChunk {
+                pattern,
+                exits,
+                has_exits,
+                compatible_with: [Vec::new(), Vec::new(), Vec::new(), Vec::new()],
+            }
+        })
+        .collect();
+
+    // 出口のある辺は向かい側の出口と1つ以上重なる相手と、出口のない辺は出口のない辺とだけつながる
+    let mut compatibility: Vec<[Vec<usize>; 4]> = Vec::with_capacity(constraints.len());
+    for chunk in constraints.iter() {
+        let mut compatible_with = [Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+        for (j, other) in constraints.iter().enumerate() {
+            for (direction, compatible) in compatible_with.iter_mut().enumerate() {
+                let opposite = opposite_direction(direction);
+                let has_any = chunk.exits[direction].iter().any(|exit| *exit);
+                let fits = if has_any {
+                    chunk.exits[direction]
+                        .iter()
+                        .zip(other.exits[opposite].iter())
+                        .any(|(mine, theirs)| *mine && *theirs)
+                } else {
+                    !other.exits[opposite].iter().any(|exit| *exit)
+                };
+
+                if fits {
+                    compatible.push(j);
+                }
+            }
+        }
+        compatibility.push(compatible_with);
+    }
+
+    for (chunk, compatible_with) in constraints.iter_mut().zip(compatibility) {
+        chunk.compatible_with = compatible_with;
+    }
+
+    constraints
+}
+
+pub fn opposite_direction(direction: usize) -> usize {
+    match direction {
+        NORTH => SOUTH,
+        SOUTH => NORTH,
+        WEST => EAST,
+        _ => WEST,
+    }
+}
+
+pub fn render_pattern_to_map(
+    map: &mut Map,
+    chunk: &MapChunk,
+    chunk_size: usize,
+    start_x: usize,
+    start_y: usize,
+) {
+    for y in 0..chunk_size {
+        for x in 0..chunk_size {
+            let idx = map.xy_idx((start_x + x) as i32, (start_y + y) as i32);
+            map.tiles[idx] = chunk.pattern[y * chunk_size + x];
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use super::*;
+
+pub struct Solver {
+    constraints: Vec<MapChunk>,
+    chunk_size: usize,
+    chunks: Vec<Option<usize>>,
+    chunks_x: usize,
+    chunks_y: usize,
+    remaining: Vec<usize>,
+    pub possible: bool,
+}
+
+impl Solver {
+    pub fn new(constraints: Vec<MapChunk>, chunk_size: usize, map: &Map) -> Self {
+        let chunks_x = map.width / chunk_size;
+        let chunks_y = map.height / chunk_size;
+
+        Self {
+            constraints,
+            chunk_size,
+            chunks: vec![None; chunks_x * chunks_y],
+            chunks_x,
+            chunks_y,
+            remaining: (0..chunks_x * chunks_y).collect(),
+            possible: true,
+        }
+    }
+
+    pub fn into_constraints(self) -> Vec<MapChunk> {
+        self.constraints
+    }
+
+    fn chunk_idx(&self, x: usize, y: usize) -> usize {
+        (y * self.chunks_x) + x
+    }
+
+    // 各方向の隣のチャンク。(方向, 決まっているパターン)
+    fn placed_neighbours(&self, chunk_index: usize) -> Vec<(usize, usize)> {
+        let x = chunk_index % self.chunks_x;
+        let y = chunk_index / self.chunks_x;
+        let mut neighbours = Vec::new();
+
+        if y > 0 {
+            if let Some(pattern) = self.chunks[self.chunk_idx(x, y - 1)] {
+                neighbours.push((NORTH, pattern));
+            }
+        }
+        if y < self.chunks_y - 1 {
+            if let Some(pattern) = self.chunks[self.chunk_idx(x, y + 1)] {
+                neighbours.push((SOUTH, pattern));
+            }
+        }
+        if x > 0 {
+            if let Some(pattern) = self.chunks[self.chunk_idx(x - 1, y)] {
+                neighbours.push((WEST, pattern));
+            }
+        }
+        if x < self.chunks_x - 1 {
+            if let Some(pattern) = self.chunks[self.chunk_idx(x + 1, y)] {
+                neighbours.push((EAST, pattern));
+            }
+        }
+
+        neighbours
+    }
+
+    // チャンクを1つ決める。すべて決まったか、矛盾して続けられなくなったらtrueを返す
+    pub fn iteration(&mut self, map: &mut Map, rng: &mut RandomNumberGenerator) -> bool {
+        if self.remaining.is_empty() {
+            return true;
+        }
+
+        // すでに決まった隣が一番多いチャンクから埋める
+        let most_neighbours = self
+            .remaining
+            .iter()
+            .map(|idx| self.placed_neighbours(*idx).len())
+            .max()
+            .unwrap();
+        let candidates: Vec<usize> = self
+            .remaining
+            .iter()
+            .filter(|idx| self.placed_neighbours(**idx).len() == most_neighbours)
+            .cloned()
+            .collect();
+        let chunk_index = candidates[(rng.roll_dice(1, candidates.len() as i32) - 1) as usize];
+        self.remaining.retain(|idx| *idx != chunk_index);
+
+        let neighbours = self.placed_neighbours(chunk_index);
+        // 最初のチャンクが壁だけにならないよう、隣がないときは出口のあるものから選ぶ
+        let options: Vec<usize> = (0..self.constraints.len())
+            .filter(|option| {
+                (!neighbours.is_empty() || self.constraints[*option].has_exits)
+                    && neighbours.iter().all(|(direction, pattern)| {
+                        self.constraints[*pattern].compatible_with[opposite_direction(*direction)]
+                            .contains(option)
+                    })
+            })
+            .collect();
+
+        if options.is_empty() {
+            self.possible = false;
+            return true;
+        }
+
+        let selected = options[(rng.roll_dice(1, options.len() as i32) - 1) as usize];
+        self.chunks[chunk_index] = Some(selected);
+
+        let start_x = (chunk_index % self.chunks_x) * self.chunk_size;
+        let start_y = (chunk_index / self.chunks_x) * self.chunk_size;
+        render_pattern_to_map(
+            map,
+            &self.constraints[selected],
+            self.chunk_size,
+            start_x,
+            start_y,
+        );
+
+        self.remaining.is_empty()
+    }
+}
@@ -66,13 +66,23 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
             VirtualKeyCode::G => get_item(gs),
             VirtualKeyCode::I => return RunState::ShowInventory,
             VirtualKeyCode::D => return RunState::ShowDropItem,
-            VirtualKeyCode::Escape => return RunState::SaveGame,
+            VirtualKeyCode::Escape => {
+                return RunState::SlotPicker {
+                    mode: gui::SlotMode::Save,
+                    selection: 0,
+                }
+            }
             VirtualKeyCode::Numpad5 | VirtualKeyCode::Space => return skip_turn(gs),
             VirtualKeyCode::Period => {
                 if try_next_level(gs) {
                     return RunState::NextLevel;
                 }
             }
+            VirtualKeyCode::Comma => {
+                if try_previous_level(gs) {
+                    return RunState::PreviousLevel;
+                }
+            }
             VirtualKeyCode::R => return RunState::ShowRemoveItem,
             _ => return RunState::AwaitingInput,
         },
@@ -99,3 +109,19 @@ pub fn try_next_level(gs: &mut State) -> bool {
         false
     }
 }
+
+pub fn try_previous_level(gs: &mut State) -> bool {
+    let player_pos = gs.world.resources.get::<Point>().unwrap();
+    let map = gs.world.resources.get::<Map>().unwrap();
+    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+
+    if map.tiles[player_idx] == TileType::UpStairs {
+        true
+    } else {
+        let mut gamelog = gs.world.resources.get_mut::<GameLog>().unwrap();
+        gamelog
+            .entries
+            .push("There is no way up from here.".to_string());
+        false
+    }
+}
@@ -1,26 +1,32 @@
 use rltk::RandomNumberGenerator;
 
-pub struct RandomEntry {
-    name: String,
-    weight: i32,
+// cumulative_weightはこのエントリまでの重みの合計
+pub struct RandomEntry<T> {
+    entry: T,
+    cumulative_weight: i32,
 }
 
-impl RandomEntry {
-    pub fn new<S: ToString>(name: S, weight: i32) -> Self {
+impl<T> RandomEntry<T> {
+    pub fn new(entry: T, cumulative_weight: i32) -> Self {
         Self {
-            name: name.to_string(),
-            weight,
+            entry,
+            cumulative_weight,
         }
     }
 }
 
-#[derive(Default)]
-pub struct RandomTable {
-    entries: Vec<RandomEntry>,
+pub struct RandomTable<T> {
+    entries: Vec<RandomEntry<T>>,
     total_weight: i32,
 }
 
-impl RandomTable {
+impl<T: Clone> Default for RandomTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> RandomTable<T> {
     pub fn new() -> Self {
         Self {
             entries: Vec::new(),
@@ -28,30 +34,99 @@ impl RandomTable {
         }
     }
 
-    pub fn add<S: ToString>(mut self, name: S, weight: i32) -> Self {
+    pub fn add(mut self, entry: T, weight: i32) -> Self {
         if weight > 0 {
             self.total_weight += weight;
-            self.entries.push(RandomEntry::new(name, weight));
+            self.entries
+                .push(RandomEntry::new(entry, self.total_weight));
         }
         self
     }
 
-    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> String {
+    // 表が空ならNoneを返す。重みの累積和を二分探索するのでO(log n)
+    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> Option<T> {
         if self.total_weight == 0 {
-            return "None".to_string();
+            return None;
+        }
+        let roll = rng.roll_dice(1, self.total_weight) - 1;
+        let index = self
+            .entries
+            .partition_point(|e| e.cumulative_weight <= roll);
+        Some(self.entries[index].entry.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLLS: usize = 100_000;
+
+    fn observed_counts(table: &RandomTable<usize>, len: usize, seed: u64) -> Vec<usize> {
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        let mut counts = vec![0; len];
+        for _ in 0..ROLLS {
+            counts[table.roll(&mut rng).unwrap()] += 1;
         }
-        let mut roll = rng.roll_dice(1, self.total_weight) - 1;
-        let mut index: usize = 0;
+        counts
+    }
 
-        while roll > 0 {
-            if roll < self.entries[index].weight {
-                return self.entries[index].name.clone();
+    // 各エントリの出現数が二項分布の期待値から標準偏差の5倍以内に収まるか確かめる
+    fn assert_matches_weights(weights: &[i32], seed: u64) {
+        let table = weights
+            .iter()
+            .enumerate()
+            .fold(RandomTable::new(), |table, (i, w)| table.add(i, *w));
+        let total: i32 = weights.iter().filter(|w| **w > 0).sum();
+        let counts = observed_counts(&table, weights.len(), seed);
+
+        for (i, weight) in weights.iter().enumerate() {
+            if *weight <= 0 {
+                assert_eq!(counts[i], 0, "entry {} has no weight but was rolled", i);
+                continue;
             }
+            let p = *weight as f64 / total as f64;
+            let expected = ROLLS as f64 * p;
+            let sigma = (ROLLS as f64 * p * (1.0 - p)).sqrt();
+            let observed = counts[i] as f64;
+            assert!(
+                (observed - expected).abs() <= 5.0 * sigma + 1.0,
+                "seed {}: entry {} rolled {} times, expected about {:.0}",
+                seed,
+                i,
+                counts[i],
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn empty_table_rolls_nothing() {
+        let table: RandomTable<usize> = RandomTable::new().add(0, 0).add(1, -3);
+        let mut rng = RandomNumberGenerator::seeded(1);
+        assert!(table.roll(&mut rng).is_none());
+    }
 
-            roll -= self.entries[index].weight;
-            index += 1;
+    #[test]
+    fn single_entry_is_always_rolled() {
+        let table = RandomTable::new().add("Goblin", 1);
+        let mut rng = RandomNumberGenerator::seeded(2);
+        for _ in 0..100 {
+            assert_eq!(table.roll(&mut rng), Some("Goblin"));
         }
+    }
 
-        "None".to_string()
+    #[test]
+    fn frequencies_follow_weights() {
+        for seed in 0..5 {
+            assert_matches_weights(&[10, 2, 7, 3, 3, 4, 3, 3, 1, 1, 2, 1], seed);
+        }
+    }
+
+    #[test]
+    fn skewed_and_skipped_weights() {
+        for seed in 0..5 {
+            assert_matches_weights(&[1, 0, 1000, -5, 1, 50], seed);
+        }
     }
 }
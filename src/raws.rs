@@ -0,0 +1,372 @@
+use super::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+mod item_structs;
+use item_structs::*;
+mod mob_structs;
+use mob_structs::*;
+mod prop_structs;
+use prop_structs::*;
+mod spawn_table_structs;
+use spawn_table_structs::*;
+
+// アイテム、モンスター、罠などの定義。再コンパイルせずに中身を追加できる
+pub const RAWS_PATH: &str = "./raws/spawns.json";
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Raws {
+    #[serde(default)]
+    pub items: Vec<RawItem>,
+    #[serde(default)]
+    pub mobs: Vec<RawMob>,
+    #[serde(default)]
+    pub props: Vec<RawProp>,
+    #[serde(default)]
+    pub spawn_table: Vec<SpawnTableEntry>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawRenderable {
+    pub glyph: String,
+    pub fg: String,
+    pub bg: String,
+    pub order: i32,
+}
+
+// 使ったときや踏んだときの効果。書いたものだけが付く
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct RawEffects {
+    pub provides_healing: Option<i32>,
+    pub ranged: Option<i32>,
+    pub damage: Option<i32>,
+    pub area_of_effect: Option<i32>,
+    pub confusion: Option<i32>,
+    pub magic_mapping: bool,
+    pub detect_monsters: Option<i32>,
+    pub teleports: bool,
+}
+
+#[derive(Debug)]
+pub enum RawsError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    DuplicateName(String),
+    InvalidGlyph { name: String, glyph: String },
+    InvalidColor { name: String, color: String },
+    UnknownSpawn(String),
+    UnknownBuilderSpawn(String),
+    InvalidDepthRange(String),
+}
+
+impl fmt::Display for RawsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RawsError::Io(e) => write!(f, "cannot read {}: {}", RAWS_PATH, e),
+            RawsError::Parse(e) => write!(f, "cannot parse {}: {}", RAWS_PATH, e),
+            RawsError::DuplicateName(name) => write!(f, "{} is defined more than once", name),
+            RawsError::InvalidGlyph { name, glyph } => {
+                write!(f, "{} has an invalid glyph {:?}", name, glyph)
+            }
+            RawsError::InvalidColor { name, color } => {
+                write!(f, "{} has an invalid color {:?}", name, color)
+            }
+            RawsError::UnknownSpawn(name) => {
+                write!(f, "spawn table refers to unknown entity {:?}", name)
+            }
+            RawsError::UnknownBuilderSpawn(name) => {
+                write!(f, "map builders place unknown entity {:?}", name)
+            }
+            RawsError::InvalidDepthRange(name) => {
+                write!(
+                    f,
+                    "spawn table entry {} has max_depth below min_depth",
+                    name
+                )
+            }
+        }
+    }
+}
+
+// 読み込んだ定義を指す。名前ではなくこれを出現表やスポーンの一覧に持たせる
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpawnType {
+    Item(usize),
+    Mob(usize),
+    Prop(usize),
+}
+
+// resourceの借用を外してからworldに追加できるよう、定義を複製して取り出す
+enum Definition {
+    Item(RawItem),
+    Mob(RawMob),
+    Prop(RawProp),
+}
+
+// 読み込んだ定義を名前で引けるようにしたもの。resourceとして持つ
+#[derive(Default)]
+pub struct RawMaster {
+    raws: Raws,
+    index: HashMap<String, SpawnType>,
+}
+
+impl RawMaster {
+    pub fn new(raws: Raws) -> Result<Self, RawsError> {
+        let mut index = HashMap::new();
+        let entries = raws
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (&item.name, &item.renderable, SpawnType::Item(i)))
+            .chain(
+                raws.mobs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, mob)| (&mob.name, &mob.renderable, SpawnType::Mob(i))),
+            )
+            .chain(
+                raws.props
+                    .iter()
+                    .enumerate()
+                    .map(|(i, prop)| (&prop.name, &prop.renderable, SpawnType::Prop(i))),
+            );
+        for (name, renderable, spawn_type) in entries {
+            validate_renderable(name, renderable)?;
+            if index.insert(name.clone(), spawn_type).is_some() {
+                return Err(RawsError::DuplicateName(name.clone()));
+            }
+        }
+
+        for entry in raws.spawn_table.iter() {
+            if !index.contains_key(&entry.name) {
+                return Err(RawsError::UnknownSpawn(entry.name.clone()));
+            }
+            if entry.max_depth.is_some_and(|max| max < entry.min_depth) {
+                return Err(RawsError::InvalidDepthRange(entry.name.clone()));
+            }
+        }
+
+        // 町の住人や扉のようにビルダーが名前で置くものも、ここで無ければ読み込みを失敗させる
+        for name in map_builders::spawn_names() {
+            if !index.contains_key(name) {
+                return Err(RawsError::UnknownBuilderSpawn(name.to_string()));
+            }
+        }
+
+        Ok(Self { raws, index })
+    }
+
+    // その深さで出現するものだけを、深さに応じた重みで並べた表
+    pub fn spawn_table_for_depth(&self, depth: i32) -> RandomTable<SpawnType> {
+        self.raws
+            .spawn_table
+            .iter()
+            .fold(RandomTable::new(), |table, entry| {
+                match entry.weight_at(depth) {
+                    Some(weight) => table.add(self.index[&entry.name], weight),
+                    None => table,
+                }
+            })
+    }
+
+    // ビルダーが名前で置くもの。spawn_namesにある名前は読み込んだときに確かめてある
+    pub fn builder_spawn(&self, name: &str) -> SpawnType {
+        self.index[name]
+    }
+
+    fn definition(&self, spawn_type: SpawnType) -> Definition {
+        match spawn_type {
+            SpawnType::Item(i) => Definition::Item(self.raws.items[i].clone()),
+            SpawnType::Mob(i) => Definition::Mob(self.raws.mobs[i].clone()),
+            SpawnType::Prop(i) => Definition::Prop(self.raws.props[i].clone()),
+        }
+    }
+
+    pub fn item_spawns(&self) -> Vec<SpawnType> {
+        (0..self.raws.items.len()).map(SpawnType::Item).collect()
+    }
+}
+
+pub fn load_raws(path: &str) -> Result<RawMaster, RawsError> {
+    let json = std::fs::read_to_string(path).map_err(RawsError::Io)?;
+    let raws: Raws = serde_json::from_str(&json).map_err(RawsError::Parse)?;
+    RawMaster::new(raws)
+}
+
+fn validate_renderable(name: &str, renderable: &RawRenderable) -> Result<(), RawsError> {
+    if renderable.glyph.chars().count() != 1 {
+        return Err(RawsError::InvalidGlyph {
+            name: name.to_string(),
+            glyph: renderable.glyph.clone(),
+        });
+    }
+    for color in [&renderable.fg, &renderable.bg].iter() {
+        if RGB::from_hex(color).is_err() {
+            return Err(RawsError::InvalidColor {
+                name: name.to_string(),
+                color: color.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+// 定義からエンティティを作る
+pub fn spawn_raw_entity(world: &mut World, spawn_type: SpawnType, pos: Position) -> Entity {
+    let definition = world
+        .resources
+        .get::<RawMaster>()
+        .unwrap()
+        .definition(spawn_type);
+    match definition {
+        Definition::Item(item) => spawn_item(world, &item, pos),
+        Definition::Mob(mob) => spawn_mob(world, &mob, pos),
+        Definition::Prop(prop) => spawn_prop(world, &prop, pos),
+    }
+}
+
+fn spawn_item(world: &mut World, item: &RawItem, pos: Position) -> Entity {
+    let entity = world.insert(
+        (SerializeMe, Item),
+        vec![(pos, renderable(&item.renderable), Name::new(&item.name))],
+    )[0];
+
+    if let Some(consumable) = &item.consumable {
+        world.add_tag(entity, Consumable);
+        apply_effects(world, entity, &consumable.effects);
+    }
+
+    if let Some(equippable) = &item.equippable {
+        world.add_component(entity, Equippable::new(equippable.slot));
+        if equippable.power_bonus != 0 {
+            world.add_component(entity, MeleePowerBonus::new(equippable.power_bonus));
+        }
+        if equippable.defense_bonus != 0 {
+            world.add_component(entity, DefenseBonus::new(equippable.defense_bonus));
+        }
+    }
+
+    entity
+}
+
+fn spawn_mob(world: &mut World, mob: &RawMob, pos: Position) -> Entity {
+    let entity = world.insert(
+        (SerializeMe,),
+        vec![(pos, renderable(&mob.renderable), Name::new(&mob.name))],
+    )[0];
+
+    if mob.hostile {
+        world.add_tag(entity, Monster);
+    }
+    if mob.blocks_tile {
+        world.add_component(entity, BlocksTile::new());
+    }
+    if let Some(stats) = &mob.stats {
+        world.add_component(
+            entity,
+            CombatStats::new(stats.max_hp, stats.hp, stats.defense, stats.power),
+        );
+    }
+    if let Some(range) = mob.vision_range {
+        world.add_component(entity, Viewshed::new(Vec::new(), range, true));
+    }
+
+    entity
+}
+
+fn spawn_prop(world: &mut World, prop: &RawProp, pos: Position) -> Entity {
+    let entity = world.insert(
+        (SerializeMe,),
+        vec![(pos, renderable(&prop.renderable), Name::new(&prop.name))],
+    )[0];
+
+    if prop.hidden {
+        world.add_component(entity, Hidden::new());
+    }
+    if prop.blocks_tile {
+        world.add_component(entity, BlocksTile::new());
+    }
+    if prop.blocks_visibility {
+        world.add_component(entity, BlocksVisibility::new());
+    }
+    if let Some(open) = prop.door_open {
+        world.add_component(entity, Door::new(open));
+    }
+    if let Some(trigger) = &prop.entry_trigger {
+        world.add_component(entity, EntryTrigger::new());
+        apply_effects(world, entity, &trigger.effects);
+    }
+
+    entity
+}
+
+fn renderable(raw: &RawRenderable) -> Renderable {
+    Renderable::new(
+        rltk::to_cp437(raw.glyph.chars().next().unwrap()),
+        RGB::from_hex(&raw.fg).unwrap(),
+        RGB::from_hex(&raw.bg).unwrap(),
+        raw.order,
+    )
+}
+
+fn apply_effects(world: &mut World, entity: Entity, effects: &RawEffects) {
+    if let Some(heal_amount) = effects.provides_healing {
+        world.add_component(entity, ProvidesHealing::new(heal_amount));
+    }
+    if let Some(range) = effects.ranged {
+        world.add_component(entity, Ranged::new(range));
+    }
+    if let Some(damage) = effects.damage {
+        world.add_component(entity, InflictsDamage::new(damage));
+    }
+    if let Some(radius) = effects.area_of_effect {
+        world.add_component(entity, AreaOfEffect::new(radius));
+    }
+    if let Some(turns) = effects.confusion {
+        world.add_component(entity, Confusion::new(turns));
+    }
+    if effects.magic_mapping {
+        world.add_component(entity, MagicMapper::new());
+    }
+    if let Some(turns) = effects.detect_monsters {
+        world.add_component(entity, DetectsMonsters::new(turns));
+    }
+    if effects.teleports {
+        world.add_component(entity, TeleportsEntity::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_raws_define_every_builder_spawn() {
+        let raw_master = load_raws(RAWS_PATH).unwrap();
+        for name in map_builders::spawn_names() {
+            let spawn_type = raw_master.builder_spawn(name);
+            let defined = match raw_master.definition(spawn_type) {
+                Definition::Item(item) => item.name,
+                Definition::Mob(mob) => mob.name,
+                Definition::Prop(prop) => prop.name,
+            };
+            assert_eq!(defined, name);
+        }
+    }
+
+    #[test]
+    fn missing_builder_spawn_is_a_load_error() {
+        let mut raws: Raws =
+            serde_json::from_str(&std::fs::read_to_string(RAWS_PATH).unwrap()).unwrap();
+        raws.props.retain(|prop| prop.name != "Door");
+        match RawMaster::new(raws) {
+            Err(RawsError::UnknownBuilderSpawn(name)) => {
+                assert_eq!(name, "Door")
+            }
+            _ => panic!("a missing door definition must fail to load"),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use super::*;
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawItem {
+    pub name: String,
+    pub renderable: RawRenderable,
+    #[serde(default)]
+    pub consumable: Option<RawConsumable>,
+    #[serde(default)]
+    pub equippable: Option<RawEquippable>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawConsumable {
+    pub effects: RawEffects,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawEquippable {
+    pub slot: EquipmentSlot,
+    #[serde(default)]
+    pub power_bonus: i32,
+    #[serde(default)]
+    pub defense_bonus: i32,
+}
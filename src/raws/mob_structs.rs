@@ -0,0 +1,30 @@
+use super::*;
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawMob {
+    pub name: String,
+    pub renderable: RawRenderable,
+    #[serde(default)]
+    pub stats: Option<RawStats>,
+    #[serde(default)]
+    pub vision_range: Option<i32>,
+    #[serde(default = "default_true")]
+    pub blocks_tile: bool,
+    // falseなら町の住人のようにMonsterタグを持たず、襲ってこない
+    #[serde(default = "default_true")]
+    pub hostile: bool,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawStats {
+    pub max_hp: i32,
+    pub hp: i32,
+    pub defense: i32,
+    pub power: i32,
+}
+
+fn default_true() -> bool {
+    true
+}
@@ -0,0 +1,25 @@
+use super::*;
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawProp {
+    pub name: String,
+    pub renderable: RawRenderable,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub blocks_tile: bool,
+    #[serde(default)]
+    pub blocks_visibility: bool,
+    // ドアなら開いているかどうか
+    #[serde(default)]
+    pub door_open: Option<bool>,
+    #[serde(default)]
+    pub entry_trigger: Option<RawEntryTrigger>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawEntryTrigger {
+    pub effects: RawEffects,
+}
@@ -0,0 +1,29 @@
+use super::*;
+
+// 出現表の1行。重みは深さがmin_depthから1増えるごとにdepth_scalingずつ増える
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnTableEntry {
+    pub name: String,
+    pub weight: i32,
+    #[serde(default = "default_min_depth")]
+    pub min_depth: i32,
+    #[serde(default)]
+    pub max_depth: Option<i32>,
+    #[serde(default)]
+    pub depth_scaling: f32,
+}
+
+impl SpawnTableEntry {
+    pub fn weight_at(&self, depth: i32) -> Option<i32> {
+        let in_range = depth >= self.min_depth && self.max_depth.is_none_or(|max| depth <= max);
+        if !in_range {
+            return None;
+        }
+        Some(self.weight + (self.depth_scaling * (depth - self.min_depth) as f32) as i32)
+    }
+}
+
+fn default_min_depth() -> i32 {
+    1
+}
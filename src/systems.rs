@@ -1,12 +1,14 @@
 use super::*;
 mod damage_system;
 mod delete_the_dead_system;
+mod dijkstra_system;
 mod inventory;
 mod map_indexing_system;
 mod melee_combat_system;
 mod monster_ai_system;
 mod player;
 pub mod save;
+mod trigger_system;
 mod visibility_system;
 
 pub struct PlayerSchedules {
@@ -29,11 +31,18 @@ pub struct Schedules {
 pub fn build_schedules() -> Schedules {
     Schedules {
         main: Schedule::builder()
+            // 開いたドアを視界に反映するため、視界の計算前にも索引を作り直す
+            .add_system(map_indexing_system::build())
+            .flush()
             .add_system(visibility_system::build())
+            .add_system(dijkstra_system::build())
+            .flush()
             .add_system(monster_ai_system::build())
             .flush()
             .add_system(map_indexing_system::build())
             .flush()
+            .add_system(trigger_system::build())
+            .flush()
             .add_system(melee_combat_system::build())
             .flush()
             .add_system(damage_system::build())
@@ -63,6 +72,20 @@ fn get_name(world: &legion::system::SubWorld, entity: Entity) -> String {
     world.get_component::<Name>(entity).unwrap().name.to_owned()
 }
 
+// 閉じたドアを開け、通行と視界をさえぎらないようにする
+fn open_door(
+    commands: &legion::command::CommandBuffer,
+    door_entity: Entity,
+    renderable: &Renderable,
+) {
+    let mut renderable = renderable.clone();
+    renderable.glyph = rltk::to_cp437('/');
+    commands.add_component(door_entity, renderable);
+    commands.add_component(door_entity, Door::new(true));
+    commands.remove_component::<BlocksTile>(door_entity);
+    commands.remove_component::<BlocksVisibility>(door_entity);
+}
+
 fn retain_tiles(map: &Map, tiles: &mut Vec<rltk::Point>) {
     tiles.retain(|p| {
         p.x > 0 && p.x < map.width as i32 - 1 && p.y > 0 && p.y < map.height as i32 - 1
@@ -13,9 +13,12 @@ pub fn build() -> SystemBox {
         .write_component::<CombatStats>()
         .build(move |commands, world, _resources, query| {
             for (entity, damage) in query.iter_entities(world) {
-                let stats: &mut CombatStats =
-                    &mut world.get_component_mut(damage.victim.entity()).unwrap();
-                stats.hp -= damage.amount;
+                // ドアや罠など体力を持たないものは巻き込まれても何も起きない
+                if let Some(mut stats) =
+                    world.get_component_mut::<CombatStats>(damage.victim.entity())
+                {
+                    stats.hp -= damage.amount;
+                }
                 commands.delete(entity);
             }
         })
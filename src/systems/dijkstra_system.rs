@@ -0,0 +1,15 @@
+use super::*;
+
+pub fn build() -> SystemBox {
+    SystemBuilder::<()>::new("DijkstraSystem")
+        .read_resource::<Map>()
+        .read_resource::<Point>()
+        .write_resource::<DistanceToPlayer>()
+        .build(
+            move |_commands, _world, (map, player_pos, distance_to_player), _query| {
+                let map: &Map = map;
+                let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+                distance_to_player.map.rebuild(map, &[player_idx]);
+            },
+        )
+}
@@ -7,17 +7,24 @@ pub fn build() -> SystemBox {
         .read_resource::<Entity>()
         .read_resource::<Map>()
         .write_resource::<GameLog>()
+        .write_resource::<RunState>()
         .read_component::<AreaOfEffect>()
         .read_component::<Confusion>()
+        .read_component::<DetectsMonsters>()
         .read_component::<Equippable>()
         .read_component::<InflictsDamage>()
+        .read_component::<MagicMapper>()
         .read_component::<Name>()
         .read_component::<ProvidesHealing>()
         .write_component::<CombatStats>()
         .write_component::<Equipped>()
         .build(
-            move |commands, world, (player_entity, map, gamelog), (item_query, equipped_query)| {
+            move |commands,
+                  world,
+                  (player_entity, map, gamelog, runstate),
+                  (item_query, equipped_query)| {
                 let player_entity: &Entity = player_entity;
+                let runstate: &mut RunState = runstate;
 
                 for (entity, use_item) in item_query.iter_entities(world) {
                     let map: &Map = map;
@@ -52,6 +59,18 @@ pub fn build() -> SystemBox {
                         }
                     }
 
+                    if world.get_component::<MagicMapper>(item).is_some() {
+                        gamelog.push("The map is revealed to you!".to_string());
+                        *runstate = RunState::MagicMapReveal { row: 0 };
+                    }
+
+                    if let Some(detects) = world.get_component::<DetectsMonsters>(item) {
+                        for target in targets.iter() {
+                            commands.add_component(*target, DetectsMonsters::new(detects.turns));
+                        }
+                        gamelog.push("You sense the presence of monsters!".to_string());
+                    }
+
                     let item_heals = world
                         .get_component::<ProvidesHealing>(item)
                         .map(|i| (*i).clone());
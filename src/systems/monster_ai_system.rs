@@ -4,25 +4,46 @@ use rltk::Point;
 pub fn build() -> SystemBox {
     SystemBuilder::<()>::new("MonsterAISystem")
         .with_query(
-            <(Write<Viewshed>, Write<Position>, Read<Name>)>::query().filter(tag::<Monster>()),
+            <(
+                Write<Viewshed>,
+                Write<Position>,
+                Read<Name>,
+                Read<CombatStats>,
+            )>::query()
+            .filter(tag::<Monster>()),
         )
+        .with_query(<(Read<Door>, Read<Position>, Read<Renderable>)>::query())
         .write_resource::<Map>()
         .read_resource::<Point>()
         .read_resource::<RunState>()
         .read_resource::<Entity>()
+        .read_resource::<DistanceToPlayer>()
         .write_component::<Confusion>()
         .build(
-            move |commands, world, (map, player_pos, runstate, player_entity), query| {
+            move |commands,
+                  world,
+                  (map, player_pos, runstate, player_entity, distance_to_player),
+                  (query, door_query)| {
                 let map: &mut Map = map;
                 let runstate: &RunState = runstate;
                 let player_pos: &Point = player_pos;
                 let player_entity: &Entity = player_entity;
+                let distance_to_player: &DistanceToPlayer = distance_to_player;
 
                 if *runstate != RunState::MonsterTurn {
                     return;
                 }
 
-                for (entity, (mut viewshed, mut pos, _name)) in query.iter_entities(world) {
+                // 閉じたドアは通れるものとして近づき、ぶつかったら開ける
+                let mut closed_doors: Vec<(Entity, usize, Renderable)> = door_query
+                    .iter_entities(world)
+                    .filter(|(_, (door, _, _))| !door.open)
+                    .map(|(entity, (_, pos, renderable))| {
+                        (entity, map.xy_idx(pos.x, pos.y), (*renderable).clone())
+                    })
+                    .collect();
+
+                for (entity, (mut viewshed, mut pos, _name, stats)) in query.iter_entities(world) {
                     let mut can_act = true;
                     let is_confused = world.get_component_mut::<Confusion>(entity);
                     if let Some(mut i_am_confused) = is_confused {
@@ -34,30 +55,51 @@ pub fn build() -> SystemBox {
                     }
 
                     if can_act {
+                        // 体力が減ると、プレイヤーが見えている間は逃げる
+                        let is_fleeing = stats.hp < stats.max_hp / 4;
+                        let can_see_player = viewshed.visible_tiles.contains(player_pos);
                         let distance = rltk::DistanceAlg::Pythagoras
                             .distance2d(Point::new(pos.x, pos.y), *player_pos);
-                        if distance < 1.5 {
+
+                        let idx = map.xy_idx(pos.x, pos.y);
+                        let step = if !can_see_player {
+                            None
+                        } else if is_fleeing {
+                            distance_to_player.map.step_away(map, idx)
+                        } else if distance < 1.5 {
                             commands.add_component(
                                 entity,
                                 WantsToMelee {
                                     target: EntityHolder::new(*player_entity),
                                 },
                             );
-                        } else if viewshed.visible_tiles.contains(player_pos) {
-                            let path = rltk::a_star_search(
-                                map.xy_idx(pos.x, pos.y) as i32,
-                                map.xy_idx(player_pos.x, player_pos.y) as i32,
-                                map,
-                            );
-                            if path.success && path.steps.len() > 1 {
-                                let mut idx = map.xy_idx(pos.x, pos.y);
-                                map.blocked[idx] = false;
-                                pos.x = (path.steps[1] % map.width as usize) as i32;
-                                pos.y = (path.steps[1] / map.width as usize) as i32;
-                                idx = map.xy_idx(pos.x, pos.y);
-                                map.blocked[idx] = true;
-                                viewshed.dirty = true;
+                            None
+                        } else {
+                            for (_, door_idx, _) in closed_doors.iter() {
+                                map.blocked[*door_idx] = false;
                             }
+                            let step = distance_to_player.map.step_toward(map, idx);
+                            for (_, door_idx, _) in closed_doors.iter() {
+                                map.blocked[*door_idx] = true;
+                            }
+                            step
+                        };
+
+                        let door_in_the_way = step.and_then(|step| {
+                            closed_doors
+                                .iter()
+                                .position(|(_, door_idx, _)| *door_idx == step)
+                        });
+                        if let Some(door_index) = door_in_the_way {
+                            let (door_entity, _, renderable) = closed_doors.remove(door_index);
+                            open_door(commands, door_entity, &renderable);
+                        } else if let Some(step) = step {
+                            map.blocked[idx] = false;
+                            pos.x = (step % map.width) as i32;
+                            pos.y = (step / map.width) as i32;
+                            map.blocked[step] = true;
+                            viewshed.dirty = true;
+                            commands.add_component(entity, EntityMoved::new());
                         }
                     }
                 }
@@ -5,6 +5,8 @@ pub fn build() -> SystemBox {
     SystemBuilder::<()>::new("MovePlayerSystem")
         .with_query(<(Write<Position>, Write<Viewshed>)>::query().filter(component::<Player>()))
         .read_component::<CombatStats>()
+        .read_component::<Door>()
+        .read_component::<Renderable>()
         .read_resource::<Map>()
         .write_resource::<Point>()
         .write_resource::<WantsToMove>()
@@ -17,11 +19,25 @@ pub fn build() -> SystemBox {
                         commands.add_component(player, WantsToMelee::new(*potential_target));
                         return;
                     }
+                    let is_closed_door = match world.get_component::<Door>(*potential_target) {
+                        Some(door) => !door.open,
+                        None => false,
+                    };
+                    if is_closed_door {
+                        let renderable = world
+                            .get_component::<Renderable>(*potential_target)
+                            .unwrap();
+                        open_door(commands, *potential_target, &renderable);
+                        viewshed.dirty = true;
+                        return;
+                    }
                 }
 
-                if map.tiles[destination_idx] != TileType::Wall {
-                    pos.x = min(79, max(0, pos.x + wants_to_move.x));
-                    pos.y = min(49, max(0, pos.y + wants_to_move.y));
+                // 町の住人など、戦えないがふさいでいるものは通り抜けない
+                if map.tiles[destination_idx].is_walkable() && !map.blocked[destination_idx] {
+                    pos.x = min(map.width as i32 - 1, max(0, pos.x + wants_to_move.x));
+                    pos.y = min(map.height as i32 - 1, max(0, pos.y + wants_to_move.y));
+                    commands.add_component(player, EntityMoved::new());
                 }
 
                 viewshed.dirty = true;
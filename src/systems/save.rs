@@ -1,5 +1,6 @@
 use super::super::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 macro_rules! iterate_components {
     ($attempt_macro: ident, $args: expr) => {
@@ -7,13 +8,20 @@ macro_rules! iterate_components {
             $args,
             (AreaOfEffect, area_of_effect),
             (BlocksTile, blocks_tile),
+            (BlocksVisibility, blocks_visibility),
             (CombatStats, combat_stats),
             (Confusion, confusion),
             (DefenseBonus, defense_bonus),
+            (DetectsMonsters, detects_monsters),
+            (Door, door),
+            (EntityMoved, entity_moved),
+            (EntryTrigger, entry_trigger),
             (Equippable, equippable),
             (Equipped, equipped),
+            (Hidden, hidden),
             (InBackpack, in_backpack),
             (InflictsDamage, inflicts_damage),
+            (MagicMapper, magic_mapper),
             (MeleePowerBonus, melee_power_bonus),
             (Name, name),
             (Player, player),
@@ -22,6 +30,7 @@ macro_rules! iterate_components {
             (Ranged, ranged),
             (Renderable, renderable),
             (SufferDamage, suffer_damage),
+            (TeleportsEntity, teleports_entity),
             (Viewshed, viewshed),
             (WantsToDropItem, wants_to_drop_item),
             (WantsToMelee, wants_to_melee),
@@ -46,7 +55,9 @@ macro_rules! iterate_tags {
 
 macro_rules! component_struct {
     ($_: expr, $(($type:ty, $member:ident)), *) => {
+        // 後から増えたコンポーネントは古いセーブデータでは空として読む
         #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
+        #[serde(default)]
         pub struct SavedComponents {
             $($member: Vec<(String, $type)>,)*
         }
@@ -56,6 +67,7 @@ macro_rules! component_struct {
 macro_rules! tag_struct {
     ($_: expr, $(($type:ty, $member:ident)), *) => {
         #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
+        #[serde(default)]
         pub struct SavedTags {
             $($member: Vec<String>,)*
         }
@@ -66,13 +78,114 @@ iterate_components! { component_struct, () }
 
 iterate_tags! { tag_struct, () }
 
+// エンティティ群をIDの文字列と一緒に保存したもの。セーブデータと階層の保存で共用する
 #[derive(Clone, Serialize, Deserialize, Default)]
-pub struct SaveData {
-    map: Map,
+pub struct SavedEntities {
     entities: Vec<String>,
     components: SavedComponents,
     tags: SavedTags,
 }
 
+macro_rules! validate_components {
+    ($args: expr, $(($type:ty, $member:ident)), *) => {
+        $(
+            let (saved, entity_ids) = $args;
+            for (entity_id, component) in saved.components.$member.iter() {
+                check_entity_id(entity_ids, entity_id)?;
+                let mut component = component.clone();
+                for member in component.entity_members() {
+                    if let Some(entity_id) = member.entity_id() {
+                        check_entity_id(entity_ids, entity_id)?;
+                    }
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! validate_tags {
+    ($args: expr, $(($type:ty, $member:ident)), *) => {
+        $(
+            let (saved, entity_ids) = $args;
+            for entity_id in saved.tags.$member.iter() {
+                check_entity_id(entity_ids, entity_id)?;
+            }
+        )*
+    };
+}
+
+impl SavedEntities {
+    // 読み込みを始める前に、知らないエンティティを指しているものがないか確かめる
+    pub fn validate(&self) -> Result<(), SaveError> {
+        let entity_ids: HashSet<&String> = self.entities.iter().collect();
+        iterate_components!(validate_components, (self, &entity_ids));
+        iterate_tags!(validate_tags, (self, &entity_ids));
+        Ok(())
+    }
+}
+
+fn check_entity_id(entity_ids: &HashSet<&String>, entity_id: &String) -> Result<(), SaveError> {
+    if entity_ids.contains(entity_id) {
+        Ok(())
+    } else {
+        Err(SaveError::MissingEntity(entity_id.to_owned()))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct SaveData {
+    seed: u64,
+    turns: u32,
+    map: Map,
+    #[serde(flatten)]
+    level: SavedEntities,
+    dungeon: MasterDungeonMap,
+}
+
+impl SaveData {
+    pub fn validate(&self) -> Result<(), SaveError> {
+        validate_map(&self.map)?;
+        self.level.validate()?;
+        self.dungeon.validate()
+    }
+}
+
+// タイル列の長さが幅と高さに合っていないと、描画やxy_idxで範囲外を読んでしまう
+fn validate_map(map: &Map) -> Result<(), SaveError> {
+    let len = map
+        .width
+        .checked_mul(map.height)
+        .filter(|len| *len > 0)
+        .ok_or_else(|| {
+            SaveError::Malformed(format!(
+                "The map of depth {} has an invalid size {}x{}.",
+                map.depth, map.width, map.height
+            ))
+        })?;
+    let columns = [
+        ("tiles", map.tiles.len()),
+        ("revealed_tiles", map.revealed_tiles.len()),
+        ("visible_tiles", map.visible_tiles.len()),
+        ("blocked", map.blocked.len()),
+    ];
+    for (name, column_len) in columns.iter() {
+        if *column_len != len {
+            return Err(SaveError::Malformed(format!(
+                "The map of depth {} has {} {} for {} tiles.",
+                map.depth, column_len, name, len
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub mod dungeon;
+pub use dungeon::MasterDungeonMap;
+pub mod error;
+pub use error::SaveError;
+pub mod format;
 pub mod load_system;
 pub mod save_system;
+pub mod slot;
+pub use slot::{SaveDirectory, SaveSlots, SlotHeader};
+pub mod version;
@@ -0,0 +1,149 @@
+use super::super::super::*;
+use super::*;
+use std::collections::HashMap;
+
+// 一度訪れた階層のマップとエンティティを深さごとに保持する
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct MasterDungeonMap {
+    levels: HashMap<i32, StoredLevel>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+struct StoredLevel {
+    map: Map,
+    entities: SavedEntities,
+}
+
+impl MasterDungeonMap {
+    pub fn validate(&self) -> Result<(), SaveError> {
+        for stored in self.levels.values() {
+            validate_map(&stored.map)?;
+            stored.entities.validate()?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! store_components {
+    ($args: expr, $(($type:ty, $member:ident)), *) => {
+        $(
+            let (saved, world, entity) = $args;
+            if let Some(component) = world.get_component::<$type>(entity) {
+                let mut component = (&*component).clone();
+                component.store_entity_id();
+                saved.components.$member.push((format!("{}", entity), component));
+            }
+        )*
+    };
+}
+
+macro_rules! store_tags {
+    ($args: expr, $(($type:ty, $member:ident)), *) => {
+        $(
+            let (saved, world, entity) = $args;
+            if world.get_tag::<$type>(entity).is_some() {
+                saved.tags.$member.push(format!("{}", entity));
+            }
+        )*
+    };
+}
+
+macro_rules! restore_components {
+    ($args: expr, $(($type:ty, $member:ident)), *) => {
+        $(
+            let (saved, entity_dic, world) = $args;
+            for (entity_id, component) in saved.components.$member.iter() {
+                // 読み込み時に確かめているので、ここで欠けていることはない
+                if let Some(entity) = entity_dic.get(entity_id) {
+                    let mut component = component.clone();
+                    if component.restore_entity(entity_dic).is_ok() {
+                        world.add_component(*entity, component);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! restore_tags {
+    ($args: expr, $(($type:ty, $member:ident)), *) => {
+        $(
+            let (saved, entity_dic, world) = $args;
+            for entity_id in saved.tags.$member.iter() {
+                if let Some(entity) = entity_dic.get(entity_id) {
+                    world.add_tag::<$type>(*entity, <$type>::default());
+                }
+            }
+        )*
+    };
+}
+
+// 現在の階層のマップと、床に置かれているエンティティ(プレイヤー以外)を保存して取り除く
+pub fn store_level(world: &mut World) {
+    let entities: Vec<Entity> = <(Read<Position>, TryRead<Player>)>::query()
+        .filter(tag::<SerializeMe>())
+        .iter_entities(world)
+        .filter(|(_, (_, player))| player.is_none())
+        .map(|(entity, _)| entity)
+        .collect();
+
+    let mut saved = SavedEntities::default();
+    for entity in entities.iter() {
+        saved.entities.push(format!("{}", entity));
+        iterate_components!(store_components, (&mut saved, &*world, *entity));
+        iterate_tags!(store_tags, (&mut saved, &*world, *entity));
+    }
+
+    let mut map = world.resources.get::<Map>().unwrap().clone();
+    map.visible_tiles = vec![false; map.tiles.len()];
+    let depth = map.depth;
+    world
+        .resources
+        .get_mut::<MasterDungeonMap>()
+        .unwrap()
+        .levels
+        .insert(
+            depth,
+            StoredLevel {
+                map,
+                entities: saved,
+            },
+        );
+
+    for entity in entities {
+        world.delete(entity);
+    }
+}
+
+// 保存済みの階層ならマップとエンティティを戻してtrueを返す
+pub fn restore_level(world: &mut World, depth: i32) -> bool {
+    let stored = world
+        .resources
+        .get_mut::<MasterDungeonMap>()
+        .unwrap()
+        .levels
+        .remove(&depth);
+    let StoredLevel { mut map, entities } = match stored {
+        Some(stored) => stored,
+        None => return false,
+    };
+
+    map.tile_content = vec![Vec::new(); map.tiles.len()];
+    map.view_blocked = vec![false; map.tiles.len()];
+    world.resources.insert(map);
+
+    let mut entity_dic = HashMap::new();
+    for entity_id in entities.entities.iter() {
+        let entity = world.insert((SerializeMe,), vec![(OldEntityID::new(entity_id),)])[0];
+        entity_dic.insert(entity_id.to_owned(), entity);
+    }
+
+    iterate_components!(restore_components, (&entities, &entity_dic, &mut *world));
+    iterate_tags!(restore_tags, (&entities, &entity_dic, &mut *world));
+
+    for entity in entity_dic.values() {
+        world.remove_component::<OldEntityID>(*entity);
+    }
+
+    true
+}
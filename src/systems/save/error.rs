@@ -0,0 +1,65 @@
+use super::version::SAVE_VERSION;
+use crate::ecs::entity_holder::MissingEntity;
+use std::fmt;
+
+// セーブデータの読み書きで起きたエラー。State::tickまで返して画面に出す
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    UnknownFormat(String),
+    UnsupportedVersion(u32),
+    OutdatedBinary(u32),
+    Malformed(String),
+    MissingEntity(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{}", e),
+            SaveError::Json(e) => write!(f, "{}", e),
+            SaveError::Binary(e) => write!(f, "{}", e),
+            SaveError::UnknownFormat(path) => write!(f, "{} is not a known save format.", path),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "Save version {} is newer than this game supports ({}).",
+                version, SAVE_VERSION
+            ),
+            SaveError::OutdatedBinary(version) => write!(
+                f,
+                "Binary save version {} cannot be converted to this game's version ({}).",
+                version, SAVE_VERSION
+            ),
+            SaveError::Malformed(reason) => write!(f, "{}", reason),
+            SaveError::MissingEntity(entity_id) => {
+                write!(f, "Unknown entity {} is referenced.", entity_id)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        SaveError::Json(e)
+    }
+}
+
+impl From<bincode::Error> for SaveError {
+    fn from(e: bincode::Error) -> Self {
+        SaveError::Binary(e)
+    }
+}
+
+impl From<MissingEntity> for SaveError {
+    fn from(e: MissingEntity) -> Self {
+        SaveError::MissingEntity(e.0)
+    }
+}
@@ -0,0 +1,205 @@
+use super::*;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+mod binary;
+mod json;
+pub mod rle;
+
+// セーブファイルの書き方。どれを使うかはファイルの拡張子で決まる
+pub trait SaveFormat {
+    fn extension(&self) -> &'static str;
+    fn write_header(&self, writer: &mut dyn Write, header: &SlotHeader) -> Result<(), SaveError>;
+    fn read_header(&self, reader: &mut dyn BufRead) -> Result<SlotHeader, SaveError>;
+    fn write_data(&self, writer: &mut dyn Write, save: &SaveData) -> Result<(), SaveError>;
+    // ファイルの先頭から読み、見出しは読み飛ばす
+    fn read_data(&self, reader: &mut dyn BufRead) -> Result<SaveData, SaveError>;
+}
+
+const FORMATS: [&dyn SaveFormat; 2] = [&binary::BinaryFormat, &json::JsonFormat];
+
+// 新しいスロットに使う形式。バイナリは古い版から変換できないので、読める形式を既定にする
+pub const DEFAULT_EXTENSION: &str = "json";
+
+pub fn format_for_extension(extension: &str) -> Option<&'static dyn SaveFormat> {
+    FORMATS
+        .iter()
+        .find(|format| format.extension() == extension)
+        .copied()
+}
+
+pub fn format_for_path(path: &Path) -> Result<&'static dyn SaveFormat, SaveError> {
+    path.extension()
+        .and_then(|extension| format_for_extension(&extension.to_string_lossy()))
+        .ok_or_else(|| SaveError::UnknownFormat(path.display().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::BufReader;
+
+    fn sample_save() -> SaveData {
+        let mut map = Map::new(3, 20, 10);
+        for (i, tile) in map.tiles.iter_mut().enumerate() {
+            if i % 20 > 4 {
+                *tile = TileType::Floor;
+            }
+        }
+        for i in 0..60 {
+            map.revealed_tiles[i] = true;
+        }
+        let payload = json!({
+            "seed": 11,
+            "turns": 25,
+            "map": map.clone(),
+            "entities": ["1", "2"],
+            "components": {
+                "player": [["1", null]],
+                "name": [["1", { "name": "Hero" }], ["2", { "name": "Dagger" }]],
+                "in_backpack": [["2", { "owner": { "entity_id": "1" } }]],
+            },
+            "tags": { "item": ["2"] },
+            "dungeon": { "levels": { "2": {
+                "map": map,
+                "entities": { "entities": ["5"], "components": {}, "tags": { "monster": ["5"] } },
+            } } },
+        });
+        version::from_envelope(json!({ "version": version::SAVE_VERSION, "data": payload }))
+            .unwrap()
+    }
+
+    fn header() -> SlotHeader {
+        SlotHeader {
+            name: "Hero".to_string(),
+            depth: 3,
+            turns: 25,
+            timestamp: 1_700_000_000,
+            hp: 17,
+            max_hp: 30,
+        }
+    }
+
+    fn encode(format: &dyn SaveFormat, save: &SaveData) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        format.write_header(&mut bytes, &header()).unwrap();
+        format.write_data(&mut bytes, save).unwrap();
+        bytes
+    }
+
+    fn as_json(save: &SaveData) -> serde_json::Value {
+        serde_json::to_value(version::to_envelope(save)).unwrap()
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        let save = sample_save();
+        for format in FORMATS.iter() {
+            let bytes = encode(*format, &save);
+            let read_header = format.read_header(&mut BufReader::new(&bytes[..])).unwrap();
+            assert_eq!(read_header, header(), "{}", format.extension());
+            let read_save = format.read_data(&mut BufReader::new(&bytes[..])).unwrap();
+            read_save.validate().unwrap();
+            assert_eq!(
+                as_json(&read_save),
+                as_json(&save),
+                "{}",
+                format.extension()
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let save = sample_save();
+        for format in FORMATS.iter() {
+            let bytes = encode(*format, &save);
+            for len in [0, bytes.len() / 3, bytes.len() - 1].iter() {
+                let result = format.read_data(&mut BufReader::new(&bytes[..*len]));
+                assert!(result.is_err(), "{} cut at {}", format.extension(), len);
+            }
+        }
+    }
+
+    #[test]
+    fn binary_is_smaller_than_json() {
+        let save = sample_save();
+        let binary = encode(format_for_extension("sav").unwrap(), &save);
+        let json = encode(format_for_extension("json").unwrap(), &save);
+        assert!(binary.len() * 4 < json.len());
+    }
+
+    #[test]
+    fn format_is_chosen_by_extension() {
+        assert_eq!(
+            format_for_path(Path::new("a/slot1.sav"))
+                .unwrap()
+                .extension(),
+            "sav"
+        );
+        assert_eq!(
+            format_for_path(Path::new("slot1.json"))
+                .unwrap()
+                .extension(),
+            "json"
+        );
+        assert!(format_for_path(Path::new("slot1.sav.tmp")).is_err());
+        assert!(format_for_path(Path::new("slot1")).is_err());
+    }
+
+    #[test]
+    fn older_binary_version_is_reported() {
+        let mut bytes = bincode::serialize(&header()).unwrap();
+        bytes.extend(bincode::serialize(&(version::SAVE_VERSION - 1)).unwrap());
+        let result = format_for_extension("sav")
+            .unwrap()
+            .read_data(&mut BufReader::new(&bytes[..]));
+        assert!(matches!(result, Err(SaveError::OutdatedBinary(_))));
+    }
+
+    #[test]
+    fn mismatched_map_size_is_malformed() {
+        let mut save = sample_save();
+        save.map.revealed_tiles.pop();
+        let bytes = encode(format_for_extension("sav").unwrap(), &save);
+        let read_save = format_for_extension("sav")
+            .unwrap()
+            .read_data(&mut BufReader::new(&bytes[..]))
+            .unwrap();
+        assert!(matches!(read_save.validate(), Err(SaveError::Malformed(_))));
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Column {
+        #[serde(with = "rle")]
+        values: Vec<u8>,
+    }
+
+    #[test]
+    fn rle_keeps_json_as_a_plain_array() {
+        let column = Column {
+            values: vec![1, 1, 1, 2],
+        };
+        let value = serde_json::to_value(&column).unwrap();
+        assert_eq!(value, json!({ "values": [1, 1, 1, 2] }));
+    }
+
+    #[test]
+    fn rle_encodes_runs_in_binary() {
+        let column = Column {
+            values: vec![7; 1000].into_iter().chain(vec![3; 24]).collect(),
+        };
+        let bytes = bincode::serialize(&column).unwrap();
+        // 長さ(8) + 2つの(個数(4), 値(1))
+        assert_eq!(bytes.len(), 8 + 2 * 5);
+        assert_eq!(bincode::deserialize::<Column>(&bytes).unwrap(), column);
+    }
+
+    #[test]
+    fn rle_rejects_oversized_runs() {
+        let runs: Vec<(u32, u8)> = vec![(u32::MAX, 0)];
+        let bytes = bincode::serialize(&runs).unwrap();
+        assert!(bincode::deserialize::<Column>(&bytes).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use super::*;
+use bincode::Options;
+
+// 壊れたファイルの長さを信じて大きな領域を確保しないよう、読む量に上限を設ける
+const MAX_SAVE_BYTES: u64 = 64 * 1024 * 1024;
+
+// bincode::serialize_intoと同じ書き方で、読む量だけを制限する
+fn read_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(MAX_SAVE_BYTES)
+}
+
+// 見出し、バージョン、本体の順にbincodeで書く。マップのタイル列は連長圧縮される
+pub struct BinaryFormat;
+
+// bincodeはflattenを読めないので、SaveDataと同じ中身を入れ子のまま持つ
+#[derive(Serialize, Deserialize)]
+struct BinarySaveData {
+    seed: u64,
+    turns: u32,
+    map: Map,
+    level: SavedEntities,
+    dungeon: MasterDungeonMap,
+}
+
+impl SaveFormat for BinaryFormat {
+    fn extension(&self) -> &'static str {
+        "sav"
+    }
+
+    fn write_header(&self, writer: &mut dyn Write, header: &SlotHeader) -> Result<(), SaveError> {
+        bincode::serialize_into(writer, header)?;
+        Ok(())
+    }
+
+    fn read_header(&self, reader: &mut dyn BufRead) -> Result<SlotHeader, SaveError> {
+        Ok(read_options().deserialize_from(reader)?)
+    }
+
+    fn write_data(&self, writer: &mut dyn Write, save: &SaveData) -> Result<(), SaveError> {
+        let save = save.clone();
+        let data = BinarySaveData {
+            seed: save.seed,
+            turns: save.turns,
+            map: save.map,
+            level: save.level,
+            dungeon: save.dungeon,
+        };
+        bincode::serialize_into(&mut *writer, &version::SAVE_VERSION)?;
+        bincode::serialize_into(writer, &data)?;
+        Ok(())
+    }
+
+    // 型の情報を持たないので、JSONのように古い版から変換することはできない。
+    // SAVE_VERSIONを上げるときは、古いBinarySaveDataの形を残してここで変換する
+    fn read_data(&self, reader: &mut dyn BufRead) -> Result<SaveData, SaveError> {
+        let _: SlotHeader = read_options().deserialize_from(&mut *reader)?;
+        let save_version: u32 = read_options().deserialize_from(&mut *reader)?;
+        if save_version > version::SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(save_version));
+        }
+        if save_version < version::SAVE_VERSION {
+            return Err(SaveError::OutdatedBinary(save_version));
+        }
+        let data: BinarySaveData = read_options().deserialize_from(reader)?;
+        Ok(SaveData {
+            seed: data.seed,
+            turns: data.turns,
+            map: data.map,
+            level: data.level,
+            dungeon: data.dungeon,
+        })
+    }
+}
@@ -0,0 +1,119 @@
+use super::*;
+use serde_json::Value;
+
+// 1行目に見出し、2行目にバージョン付きの本体を書く。手で読んで調べられる。
+// 見出しの無い古いファイルは1行目がそのまま本体になっている
+pub struct JsonFormat;
+
+// 1行目が見出しでなければ、残りとつなげて本体として読む
+fn read_body(first_line: String, reader: &mut dyn BufRead) -> Result<Option<Value>, SaveError> {
+    if serde_json::from_str::<SlotHeader>(&first_line).is_ok() {
+        return Ok(None);
+    }
+    let mut text = first_line;
+    reader.read_to_string(&mut text)?;
+    Ok(Some(serde_json::from_str(&text)?))
+}
+
+impl SaveFormat for JsonFormat {
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn write_header(&self, writer: &mut dyn Write, header: &SlotHeader) -> Result<(), SaveError> {
+        serde_json::to_writer(&mut *writer, header)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+
+    fn read_header(&self, reader: &mut dyn BufRead) -> Result<SlotHeader, SaveError> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        match read_body(line.clone(), reader)? {
+            None => Ok(serde_json::from_str(&line)?),
+            // 古いファイルは中身を全部読んで見出しを作る
+            Some(value) => SlotHeader::from_save(&version::from_envelope(value)?),
+        }
+    }
+
+    fn write_data(&self, writer: &mut dyn Write, save: &SaveData) -> Result<(), SaveError> {
+        serde_json::to_writer(writer, &version::to_envelope(save))?;
+        Ok(())
+    }
+
+    fn read_data(&self, reader: &mut dyn BufRead) -> Result<SaveData, SaveError> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let value = match read_body(line, reader)? {
+            Some(value) => value,
+            None => serde_json::from_reader(reader)?,
+        };
+        version::from_envelope(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::BufReader;
+
+    // スロットができる前のsavegame.jsonと同じく、見出しの行が無い
+    fn legacy_file(data: Value) -> String {
+        let mut data = data;
+        data["map"] = json!(Map::new(4, 4, 4));
+        data["entities"] = json!(["1"]);
+        data["components"] = json!({
+            "player": [["1", null]],
+            "name": [["1", { "name": "Hero" }]],
+            "combat_stats": [["1", { "max_hp": 30, "hp": 17, "defense": 2, "power": 5 }]],
+        });
+        data["tags"] = json!({});
+        data.to_string()
+    }
+
+    fn read(text: &str) -> (SlotHeader, SaveData) {
+        let header = JsonFormat
+            .read_header(&mut BufReader::new(text.as_bytes()))
+            .unwrap();
+        let save = JsonFormat
+            .read_data(&mut BufReader::new(text.as_bytes()))
+            .unwrap();
+        (header, save)
+    }
+
+    #[test]
+    fn headerless_unversioned_save_is_read() {
+        let (header, save) = read(&legacy_file(json!({})));
+        assert_eq!(header.name, "Hero");
+        assert_eq!((header.depth, header.hp, header.max_hp), (4, 17, 30));
+        assert_eq!(header.timestamp, 0);
+        assert_eq!(save.seed, 0);
+        save.validate().unwrap();
+    }
+
+    #[test]
+    fn headerless_envelope_is_read() {
+        let data: Value = serde_json::from_str(&legacy_file(json!({
+            "seed": 3,
+            "dungeon": { "levels": {} },
+        })))
+        .unwrap();
+        let text = json!({ "version": 1, "data": data }).to_string();
+        let (header, save) = read(&text);
+        assert_eq!(header.name, "Hero");
+        assert_eq!((save.seed, save.turns), (3, 0));
+    }
+
+    #[test]
+    fn file_with_header_is_read() {
+        let (_, save) = read(&legacy_file(json!({})));
+        let header = SlotHeader::from_save(&save).unwrap();
+        let mut bytes: Vec<u8> = Vec::new();
+        JsonFormat.write_header(&mut bytes, &header).unwrap();
+        JsonFormat.write_data(&mut bytes, &save).unwrap();
+        let (read_header, read_save) = read(std::str::from_utf8(&bytes).unwrap());
+        assert_eq!(read_header, header);
+        assert_eq!(read_save.map.depth, 4);
+    }
+}
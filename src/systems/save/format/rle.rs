@@ -0,0 +1,50 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// 展開した長さの上限。どの階層のマップよりも十分に大きい
+pub const MAX_DECODED_LEN: usize = 1 << 20;
+
+// マップのタイル列に`#[serde(with = "rle")]`で付ける。
+// JSONでは今まで通りの配列のまま、バイナリでは(個数, 値)の並びにする
+pub fn serialize<T, S>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize + PartialEq,
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        return values.serialize(serializer);
+    }
+
+    let mut runs: Vec<(u32, &T)> = Vec::new();
+    for value in values {
+        match runs.last_mut() {
+            Some((count, last)) if *last == value => *count += 1,
+            _ => runs.push((1, value)),
+        }
+    }
+    runs.serialize(serializer)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T: Deserialize<'de> + Clone,
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        return Vec::deserialize(deserializer);
+    }
+
+    let runs: Vec<(u32, T)> = Vec::deserialize(deserializer)?;
+    let mut values = Vec::new();
+    for (count, value) in runs {
+        // 壊れた個数をそのまま信じると巨大な領域を確保してしまう
+        if values.len() + count as usize > MAX_DECODED_LEN {
+            return Err(D::Error::custom(format!(
+                "run-length encoded tiles exceed {} entries",
+                MAX_DECODED_LEN
+            )));
+        }
+        values.extend(std::iter::repeat_n(value, count as usize));
+    }
+    Ok(values)
+}
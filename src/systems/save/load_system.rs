@@ -2,6 +2,8 @@ use super::super::*;
 use super::*;
 use std::collections::HashMap;
 use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
 
 pub fn schedule() -> legion::schedule::Schedule {
     Schedule::builder()
@@ -14,20 +16,27 @@ pub fn schedule() -> legion::schedule::Schedule {
         .build()
 }
 
+// スケジュールを実行する前に読んでおく。読めなければworldには触らない
+pub fn read_save(path: &Path) -> Result<SaveData, SaveError> {
+    let format = format::format_for_path(path)?;
+    let mut reader = BufReader::new(File::open(path)?);
+    let save_data = format.read_data(&mut reader)?;
+    save_data.validate()?;
+    Ok(save_data)
+}
+
 pub fn initialize_entities() -> SystemBox {
     SystemBuilder::<()>::new("InitializeEntities")
         .with_query(<Tagged<SerializeMe>>::query())
-        .write_resource::<SaveData>()
+        .read_resource::<SaveData>()
         .build(move |commands, world, save_data, query| {
-            let save_data: &mut SaveData = save_data;
+            let save_data: &SaveData = save_data;
 
             for (entity, _) in query.iter_entities(world) {
                 commands.delete(entity);
             }
 
-            *save_data = serde_json::from_reader(File::open("./savegame.json").unwrap()).unwrap();
-
-            for entity_id in &save_data.entities {
+            for entity_id in &save_data.level.entities {
                 commands.insert((SerializeMe,), vec![(OldEntityID::new(entity_id),)]);
             }
         })
@@ -39,10 +48,13 @@ macro_rules! load_components {
             let (save_data, entity_dic, commands) = $args;
 
             for (entity_id, component) in save_data.components.$member.iter() {
-                let entity = entity_dic.get(entity_id).unwrap().clone();
-                let mut component = component.clone();
-                component.restore_entity(entity_dic);
-                commands.add_component(entity, component);
+                // read_saveで確かめているので、ここで欠けていることはない
+                if let Some(entity) = entity_dic.get(entity_id) {
+                    let mut component = component.clone();
+                    if component.restore_entity(entity_dic).is_ok() {
+                        commands.add_component(*entity, component);
+                    }
+                }
             }
         )*
     };
@@ -54,9 +66,10 @@ macro_rules! load_tags {
             let (save_data, entity_dic, commands) = $args;
 
             for entity_id in save_data.tags.$member.iter() {
-                let entity = entity_dic.get(entity_id).unwrap().clone();
-                let tag = <$type>::default();
-                commands.add_tag::<$type>(entity, tag);
+                if let Some(entity) = entity_dic.get(entity_id) {
+                    let tag = <$type>::default();
+                    commands.add_tag::<$type>(*entity, tag);
+                }
             }
         )*
     };
@@ -67,22 +80,37 @@ pub fn load_components() -> SystemBox {
         .with_query(<Read<OldEntityID>>::query())
         .write_resource::<SaveData>()
         .write_resource::<Map>()
-        .build(move |commands, world, (save_data, map), query| {
-            let save_data: &mut SaveData = save_data;
-            let mut entity_dic = HashMap::new();
+        .write_resource::<GameSeed>()
+        .write_resource::<MasterDungeonMap>()
+        .write_resource::<TurnCount>()
+        .build(
+            move |commands, world, (save_data, map, game_seed, dungeon, turn_count), query| {
+                let save_data: &mut SaveData = save_data;
+                let mut entity_dic = HashMap::new();
 
-            for (entity, old_entity_id) in query.iter_entities(world) {
-                entity_dic.insert(old_entity_id.entity_id.to_owned(), entity);
-            }
+                for (entity, old_entity_id) in query.iter_entities(world) {
+                    entity_dic.insert(old_entity_id.entity_id.to_owned(), entity);
+                }
 
-            iterate_components!(load_components, (&save_data, &entity_dic, &commands));
+                iterate_components!(load_components, (&save_data.level, &entity_dic, &commands));
 
-            iterate_tags!(load_tags, (&save_data, &entity_dic, &commands));
+                iterate_tags!(load_tags, (&save_data.level, &entity_dic, &commands));
 
-            let map: &mut Map = map;
-            *map = save_data.map.clone();
-            map.tile_content = vec![Vec::new(); super::super::map::MAPCOUNT];
-        })
+                let map: &mut Map = map;
+                *map = save_data.map.clone();
+                map.tile_content = vec![Vec::new(); map.tiles.len()];
+                map.view_blocked = vec![false; map.tiles.len()];
+
+                let game_seed: &mut GameSeed = game_seed;
+                game_seed.seed = save_data.seed;
+
+                let turn_count: &mut TurnCount = turn_count;
+                turn_count.turns = save_data.turns;
+
+                let dungeon: &mut MasterDungeonMap = dungeon;
+                *dungeon = save_data.dungeon.clone();
+            },
+        )
 }
 
 pub fn write_player_resource() -> SystemBox {
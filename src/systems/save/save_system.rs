@@ -1,7 +1,9 @@
 use super::super::*;
 use super::*;
+use format::SaveFormat;
 use std::fs::File;
-use std::path::Path;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
 
 macro_rules! serialize_tags {
     ($args: expr, $(($type:ty, $member:ident)), *) => {
@@ -19,24 +21,32 @@ macro_rules! serialize_individually {
         SystemBuilder::<()>::new("SaveSystem")
             $(.read_component::<$type>())*
             .read_resource::<Map>()
+            .read_resource::<GameSeed>()
+            .read_resource::<MasterDungeonMap>()
+            .read_resource::<TurnCount>()
+            .write_resource::<SaveData>()
             .with_query(<Tagged<SerializeMe>>::query())
-            .build(move |_commands, world, map, query| {
+            .build(move |_commands, world, (map, game_seed, dungeon, turn_count, save_data), query| {
                 let mut save = SaveData::default();
                 let map: &Map = map;
                 save.map = map.clone();
+                save.seed = game_seed.seed;
+                save.turns = turn_count.turns;
+                let dungeon: &MasterDungeonMap = dungeon;
+                save.dungeon = dungeon.clone();
                 for (entity, _) in query.iter_entities(world) {
-                    save.entities.push(format!("{}", entity));
+                    save.level.entities.push(format!("{}", entity));
                     $(
                         if let Some(component) = world.get_component::<$type>(entity) {
                             let mut component = (&*component).clone();
                             component.store_entity_id();
-                            save.components.$member.push((format!("{}", entity), component));
+                            save.level.components.$member.push((format!("{}", entity), component));
                         }
                     )*
-                    iterate_tags!(serialize_tags, (&mut save, &world, entity));
+                    iterate_tags!(serialize_tags, (&mut save.level, &world, entity));
                 }
-                let writer = File::create("./savegame.json").unwrap();
-                serde_json::to_writer(writer, &save).unwrap();
+                let save_data: &mut SaveData = save_data;
+                *save_data = save;
             })
     };
 }
@@ -45,12 +55,79 @@ pub fn build() -> SystemBox {
     iterate_components!(serialize_individually, ())
 }
 
-pub fn does_save_exist() -> bool {
-    Path::new("./savegame.json").exists()
+// スケジュールで集めたSaveDataを、先頭に見出しを付けてスロットに書き出す。
+// 書き方は拡張子で決まる。失敗はState::tickで知らせる
+pub fn write_save(world: &World, path: &Path) -> Result<(), SaveError> {
+    let format = format::format_for_path(path)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    std::fs::create_dir_all(dir)?;
+
+    // 途中で落ちても元のセーブが残るよう、一時ファイルに書き切ってから置き換える
+    let temp_path = with_suffix(path, "tmp");
+    if let Err(e) = write_synced(world, format, &temp_path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    let backup = world.resources.get::<SaveDirectory>().unwrap().backup;
+    if let Err(e) = replace_save(path, &temp_path, backup) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    // 置き換えた時点でセーブは済んでいるので、ここで失敗しても保存し直させない
+    if let Err(e) = sync_dir(dir) {
+        let mut log = world.resources.get_mut::<GameLog>().unwrap();
+        log.entries.push(format!(
+            "Warning: the save may not be flushed to disk: {}",
+            e
+        ));
+    }
+    Ok(())
+}
+
+fn write_synced(world: &World, format: &dyn SaveFormat, path: &Path) -> Result<(), SaveError> {
+    let save_data = world.resources.get::<SaveData>().unwrap();
+    let mut writer = BufWriter::new(File::create(path)?);
+    format.write_header(&mut writer, &SlotHeader::from_world(world))?;
+    format.write_data(&mut writer, &save_data)?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    Ok(())
+}
+
+// 書き終えた一時ファイルで置き換える。backupなら置き換える前のものを.bakに写しておく
+fn replace_save(path: &Path, temp_path: &Path, backup: bool) -> std::io::Result<()> {
+    if backup && path.exists() {
+        std::fs::copy(path, with_suffix(path, "bak"))?;
+    }
+    std::fs::rename(temp_path, path)
+}
+
+// slot1.savならslot1.sav.tmpのように拡張子を足す。スロットの一覧には出ない
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// 名前の付け替えもディスクに書かれるようにする。Windowsではディレクトリを開けない
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<(), SaveError> {
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<(), SaveError> {
+    Ok(())
 }
 
-pub fn delete_save() {
-    if Path::new("./savegame.json").exists() {
-        std::fs::remove_file("./savegame.json").expect("Unable to delete file");
+pub fn delete_save(path: &Path) -> Result<(), SaveError> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
     }
+    Ok(())
 }
@@ -0,0 +1,227 @@
+use super::super::super::*;
+use super::*;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// `--save-dir <パス>`で起動するとセーブデータをそこに置く
+const DEFAULT_SAVE_DIR: &str = "./saves";
+// スロットができる前はセーブデータがここに1つだけあった
+const LEGACY_SAVE_PATH: &str = "./savegame.json";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveDirectory {
+    pub path: PathBuf,
+    // 新しく作るスロットの拡張子。`--save-format sav`で小さいバイナリにできる
+    pub extension: String,
+    // `--save-backup`で起動すると、上書きする前のセーブを`.bak`として残す。
+    // 残すのはスロットごとに直前の1つだけで、保存するたびに上書きされる
+    pub backup: bool,
+}
+
+// 階段を降りるたびに上書きされるスロット
+pub const AUTOSAVE_SLOT: &str = "autosave";
+
+impl SaveDirectory {
+    pub fn new(path: PathBuf, extension: &str, backup: bool) -> Self {
+        Self {
+            path,
+            extension: extension.to_string(),
+            backup,
+        }
+    }
+
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let arg_value = |name: &str| {
+            args.iter()
+                .position(|arg| arg == name)
+                .and_then(|i| args.get(i + 1))
+        };
+        let path =
+            arg_value("--save-dir").map_or_else(|| PathBuf::from(DEFAULT_SAVE_DIR), PathBuf::from);
+        let extension = arg_value("--save-format")
+            .filter(|extension| format::format_for_extension(extension).is_some())
+            .map_or(format::DEFAULT_EXTENSION, |extension| extension.as_str());
+        let backup = args.iter().any(|arg| arg == "--save-backup");
+        Self::new(path, extension, backup)
+    }
+
+    pub fn slot_path(&self, name: &str) -> PathBuf {
+        self.path.join(name).with_extension(&self.extension)
+    }
+
+    // 新しいスロットには空いている番号で名前を付ける
+    pub fn new_slot_path(&self) -> PathBuf {
+        (1..)
+            .map(|i| self.slot_path(&format!("slot{}", i)))
+            .find(|path| !path.exists())
+            .unwrap()
+    }
+
+    // 古い場所のセーブデータをスロットとして読めるよう、セーブ用のディレクトリに移す
+    pub fn import_legacy_save(&self) -> Result<Option<PathBuf>, SaveError> {
+        let legacy = Path::new(LEGACY_SAVE_PATH);
+        if !legacy.exists() {
+            return Ok(None);
+        }
+        std::fs::create_dir_all(&self.path)?;
+        let path = (1..)
+            .map(|i| match i {
+                1 => self.path.join("savegame.json"),
+                _ => self.path.join(format!("savegame{}.json", i)),
+            })
+            .find(|path| !path.exists())
+            .unwrap();
+        // 別のファイルシステムへは名前の付け替えで移せないので写してから消す
+        if std::fs::rename(legacy, &path).is_err() {
+            std::fs::copy(legacy, &path)?;
+            std::fs::remove_file(legacy)?;
+        }
+        Ok(Some(path))
+    }
+
+    // 見出しだけを読んで、新しい順に並べる。読めない見出しはそのまま理由を持たせる
+    pub fn list_slots(&self) -> Vec<SaveSlot> {
+        let entries = match std::fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        let mut slots: Vec<SaveSlot> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| format::format_for_path(path).is_ok())
+            .filter_map(|path| {
+                let name = path.file_name()?.to_string_lossy().into_owned();
+                let header = read_header(&path).map_err(|e| e.to_string());
+                Some(SaveSlot { name, path, header })
+            })
+            .collect();
+        slots.sort_by_key(|slot| {
+            let timestamp = slot.header.as_ref().map_or(0, |header| header.timestamp);
+            (std::cmp::Reverse(timestamp), slot.name.clone())
+        });
+        slots
+    }
+}
+
+// メインメニューとスロットを選ぶ画面で使う一覧。resourceとして持つ
+#[derive(Clone, Debug, Default)]
+pub struct SaveSlots {
+    pub slots: Vec<SaveSlot>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SaveSlot {
+    pub name: String,
+    pub path: PathBuf,
+    pub header: Result<SlotHeader, String>,
+}
+
+// セーブファイルの1行目に書く見出し。スロットを選ぶ画面はこれだけを読む
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SlotHeader {
+    pub name: String,
+    pub depth: i32,
+    pub turns: u32,
+    pub timestamp: u64,
+    pub hp: i32,
+    pub max_hp: i32,
+}
+
+impl SlotHeader {
+    // 見出しの無い古いセーブデータのために、中身のプレイヤーから見出しを作る。
+    // 保存した日時は分からないので0にしておき、read_headerでファイルの更新日時を使う
+    pub fn from_save(save: &SaveData) -> Result<Self, SaveError> {
+        let components = &save.level.components;
+        let player = components
+            .player
+            .first()
+            .map(|(entity_id, _)| entity_id)
+            .ok_or_else(|| SaveError::Malformed("The save has no player.".to_string()))?;
+        let name = components
+            .name
+            .iter()
+            .find(|(entity_id, _)| entity_id == player)
+            .map_or_else(|| "Player".to_string(), |(_, name)| name.name.clone());
+        let (hp, max_hp) = components
+            .combat_stats
+            .iter()
+            .find(|(entity_id, _)| entity_id == player)
+            .map_or((0, 0), |(_, stats)| (stats.hp, stats.max_hp));
+        Ok(Self {
+            name,
+            depth: save.map.depth,
+            turns: save.turns,
+            timestamp: 0,
+            hp,
+            max_hp,
+        })
+    }
+
+    pub fn from_world(world: &World) -> Self {
+        let player = *world.resources.get::<Entity>().unwrap();
+        let name = world.get_component::<Name>(player).unwrap().name.clone();
+        let stats = world.get_component::<CombatStats>(player).unwrap();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self {
+            name,
+            depth: world.resources.get::<Map>().unwrap().depth,
+            turns: world.resources.get::<TurnCount>().unwrap().turns,
+            timestamp,
+            hp: stats.hp,
+            max_hp: stats.max_hp,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} depth {} turn {} HP {}/{}",
+            self.name, self.depth, self.turns, self.hp, self.max_hp
+        )
+    }
+
+    // 外部のクレートを使わずにUTCの日時にする
+    pub fn saved_at(&self) -> String {
+        let days = (self.timestamp / 86400) as i64;
+        let seconds = self.timestamp % 86400;
+        let (year, month, day) = civil_from_days(days);
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds / 60 % 60
+        )
+    }
+}
+
+pub fn read_header(path: &Path) -> Result<SlotHeader, SaveError> {
+    let format = format::format_for_path(path)?;
+    let mut header = format.read_header(&mut BufReader::new(File::open(path)?))?;
+    if header.timestamp == 0 {
+        header.timestamp = std::fs::metadata(path)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+    }
+    Ok(header)
+}
+
+// 1970-01-01からの日数を年月日にする(Howard Hinnantのcivil_from_days)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
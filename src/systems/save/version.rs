@@ -0,0 +1,145 @@
+use super::*;
+use serde_json::{json, Value};
+
+// セーブデータの形式を変えたら上げ、古い形式からの変換をMIGRATIONSの末尾に足す
+pub const SAVE_VERSION: u32 = 2;
+
+// MIGRATIONS[n]はバージョンnのデータをn+1に変換する
+const MIGRATIONS: [fn(Value) -> Result<Value, SaveError>; SAVE_VERSION as usize] =
+    [from_unversioned, add_turn_count];
+
+#[derive(Serialize)]
+struct SaveEnvelopeRef<'a> {
+    version: u32,
+    data: &'a SaveData,
+}
+
+#[derive(Deserialize)]
+struct SaveEnvelope {
+    version: u32,
+    data: Value,
+}
+
+pub fn to_envelope(save: &SaveData) -> impl Serialize + '_ {
+    SaveEnvelopeRef {
+        version: SAVE_VERSION,
+        data: save,
+    }
+}
+
+// バージョンを確かめ、古ければ順に変換してから読む
+pub fn from_envelope(value: Value) -> Result<SaveData, SaveError> {
+    let (version, mut data) = match value.get("version") {
+        // バージョンを持たない頃のセーブデータは中身がそのまま書かれている
+        None => (0, value),
+        Some(version) => {
+            if version.as_u64().is_none_or(|v| v > u64::from(u32::MAX)) {
+                return Err(SaveError::Malformed(
+                    "Save data has an invalid version.".to_string(),
+                ));
+            }
+            let envelope: SaveEnvelope = serde_json::from_value(value)?;
+            (envelope.version, envelope.data)
+        }
+    };
+    if version > SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+
+    for migration in MIGRATIONS[version as usize..].iter() {
+        data = migration(data)?;
+    }
+    Ok(serde_json::from_value(data)?)
+}
+
+// シードと訪れた階層は後から増えたので、無ければ空で埋める
+fn from_unversioned(mut data: Value) -> Result<Value, SaveError> {
+    let object = data
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Malformed("Save data is not an object.".to_string()))?;
+    object.entry("seed").or_insert(json!(0));
+    object.entry("dungeon").or_insert(json!({ "levels": {} }));
+    Ok(data)
+}
+
+// 経過ターン数は後から増えたので0から数える
+fn add_turn_count(mut data: Value) -> Result<Value, SaveError> {
+    let object = data
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Malformed("Save data is not an object.".to_string()))?;
+    object.entry("turns").or_insert(json!(0));
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> Value {
+        json!({
+            "map": Map::new(1, 4, 4),
+            "entities": ["1"],
+            "components": { "name": [["1", { "name": "Player" }]] },
+            "tags": {},
+        })
+    }
+
+    #[test]
+    fn unversioned_payload_is_migrated() {
+        let save = from_envelope(payload()).unwrap();
+        assert_eq!(save.seed, 0);
+        assert_eq!(save.turns, 0);
+        assert_eq!(save.level.entities, vec!["1".to_string()]);
+        save.validate().unwrap();
+    }
+
+    #[test]
+    fn version_one_envelope_is_migrated() {
+        let mut data = payload();
+        data["seed"] = json!(42);
+        data["dungeon"] = json!({ "levels": {} });
+        let save = from_envelope(json!({ "version": 1, "data": data })).unwrap();
+        assert_eq!(save.seed, 42);
+        assert_eq!(save.turns, 0);
+    }
+
+    #[test]
+    fn current_envelope_round_trips() {
+        let mut save = from_envelope(payload()).unwrap();
+        save.seed = 7;
+        save.turns = 12;
+        let value = serde_json::to_value(to_envelope(&save)).unwrap();
+        assert_eq!(value["version"], json!(SAVE_VERSION));
+        let loaded = from_envelope(value).unwrap();
+        assert_eq!((loaded.seed, loaded.turns), (7, 12));
+    }
+
+    #[test]
+    fn future_version_is_unsupported() {
+        let envelope = json!({ "version": SAVE_VERSION + 1, "data": payload() });
+        match from_envelope(envelope) {
+            Err(SaveError::UnsupportedVersion(version)) => assert_eq!(version, SAVE_VERSION + 1),
+            _ => panic!("a newer save version must be rejected"),
+        }
+    }
+
+    #[test]
+    fn invalid_version_is_malformed() {
+        for version in [json!("2"), json!(-1), json!(1.5), json!(null)].iter() {
+            let envelope = json!({ "version": version, "data": payload() });
+            assert!(
+                matches!(from_envelope(envelope), Err(SaveError::Malformed(_))),
+                "version {}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn non_object_payload_is_malformed() {
+        assert!(matches!(
+            from_envelope(json!([1, 2, 3])),
+            Err(SaveError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,96 @@
+use super::*;
+
+// 溶岩に踏み込んだときのダメージ
+const LAVA_DAMAGE: i32 = 5;
+
+// 移動したエンティティが罠などのEntryTriggerや溶岩を踏んだら効果を発動する
+pub fn build() -> SystemBox {
+    SystemBuilder::<()>::new("TriggerSystem")
+        .with_query(<(Read<EntityMoved>, Read<Position>)>::query())
+        .read_component::<Confusion>()
+        .read_component::<EntryTrigger>()
+        .read_component::<InflictsDamage>()
+        .read_component::<Name>()
+        .read_component::<TeleportsEntity>()
+        .read_component::<Viewshed>()
+        .read_resource::<Map>()
+        .read_resource::<Entity>()
+        .write_resource::<Point>()
+        .write_resource::<GameLog>()
+        .write_resource::<RandomNumberGenerator>()
+        .build(
+            move |commands, world, (map, player_entity, player_pos, gamelog, rng), query| {
+                let map: &Map = map;
+                let player_entity: &Entity = player_entity;
+                let player_pos: &mut Point = player_pos;
+                let rng: &mut RandomNumberGenerator = rng;
+
+                let moved: Vec<(Entity, usize)> = query
+                    .iter_entities(world)
+                    .map(|(entity, (_, pos))| (entity, map.xy_idx(pos.x, pos.y)))
+                    .collect();
+
+                for (entity, idx) in moved {
+                    commands.remove_component::<EntityMoved>(entity);
+
+                    if map.tiles[idx].is_hazardous() {
+                        if map.visible_tiles[idx] {
+                            gamelog.push(format!(
+                                "{} is burned by the lava!",
+                                get_name(world, entity)
+                            ));
+                        }
+                        SufferDamage::new_damage(commands, entity, LAVA_DAMAGE);
+                    }
+
+                    for trap in map.tile_content[idx].iter() {
+                        if *trap == entity || world.get_component::<EntryTrigger>(*trap).is_none() {
+                            continue;
+                        }
+
+                        commands.remove_component::<Hidden>(*trap);
+                        if map.visible_tiles[idx] {
+                            gamelog.push(format!(
+                                "{} triggers {}!",
+                                get_name(world, entity),
+                                get_name(world, *trap)
+                            ));
+                        }
+
+                        if let Some(damage) = world.get_component::<InflictsDamage>(*trap) {
+                            SufferDamage::new_damage(commands, entity, damage.damage);
+                        }
+
+                        if let Some(confusion) = world.get_component::<Confusion>(*trap) {
+                            commands.add_component(entity, Confusion::new(confusion.turns));
+                        }
+
+                        if world.get_component::<TeleportsEntity>(*trap).is_some() {
+                            let destination = random_open_tile(map, rng);
+                            commands
+                                .add_component(entity, Position::new(destination.x, destination.y));
+                            if let Some(viewshed) = world.get_component::<Viewshed>(entity) {
+                                let mut viewshed = (*viewshed).clone();
+                                viewshed.dirty = true;
+                                commands.add_component(entity, viewshed);
+                            }
+                            if entity == *player_entity {
+                                *player_pos = destination;
+                            }
+                        }
+                    }
+                }
+            },
+        )
+}
+
+fn random_open_tile(map: &Map, rng: &mut RandomNumberGenerator) -> Point {
+    loop {
+        let x = rng.roll_dice(1, map.width as i32 - 2);
+        let y = rng.roll_dice(1, map.height as i32 - 2);
+        let idx = map.xy_idx(x, y);
+        if map.tiles[idx] == TileType::Floor && !map.blocked[idx] {
+            return Point::new(x, y);
+        }
+    }
+}
@@ -4,31 +4,75 @@ use rltk::{field_of_view, Point};
 pub fn build() -> SystemBox {
     SystemBuilder::<()>::new("VisibilitySystem")
         .with_query(<(Write<Viewshed>, Read<Position>, TryRead<Player>)>::query())
+        .read_component::<Hidden>()
+        .write_component::<DetectsMonsters>()
+        .read_resource::<Entity>()
+        .read_component::<Name>()
         .write_resource::<Map>()
-        .build(move |_commands, world, map, query| {
-            let map: &mut Map = map;
-            for (mut viewshed, pos, player) in query.iter(world) {
-                if viewshed.dirty {
-                    viewshed.dirty = false;
-                    viewshed.visible_tiles.clear();
-                    viewshed.visible_tiles =
-                        field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
-                    retain_tiles(&map, &mut viewshed.visible_tiles);
+        .read_resource::<RunState>()
+        .write_resource::<GameLog>()
+        .write_resource::<RandomNumberGenerator>()
+        .build(
+            move |commands, world, (player_entity, map, runstate, gamelog, rng), query| {
+                let map: &mut Map = map;
+                let player_entity: &Entity = player_entity;
+                let runstate: &RunState = runstate;
+                let rng: &mut RandomNumberGenerator = rng;
+                let mut player_visible_tiles = Vec::new();
+                for (mut viewshed, pos, player) in query.iter(world) {
+                    if player.is_some() {
+                        player_visible_tiles = viewshed.visible_tiles.clone();
+                    }
+                    if viewshed.dirty {
+                        viewshed.dirty = false;
+                        viewshed.visible_tiles.clear();
+                        viewshed.visible_tiles =
+                            field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
+                        retain_tiles(&map, &mut viewshed.visible_tiles);
+
+                        if player.is_none() {
+                            continue;
+                        }
 
-                    if player.is_none() {
-                        continue;
+                        for t in map.visible_tiles.iter_mut() {
+                            *t = false
+                        }
+
+                        for vis in viewshed.visible_tiles.iter() {
+                            let idx = map.xy_idx(vis.x, vis.y);
+                            map.revealed_tiles[idx] = true;
+                            map.visible_tiles[idx] = true;
+                        }
+                        player_visible_tiles = viewshed.visible_tiles.clone();
                     }
+                }
 
-                    for t in map.visible_tiles.iter_mut() {
-                        *t = false
+                if *runstate != RunState::PlayerTurn {
+                    return;
+                }
+
+                if let Some(mut detects) =
+                    world.get_component_mut::<DetectsMonsters>(*player_entity)
+                {
+                    detects.turns -= 1;
+                    if detects.turns < 1 {
+                        commands.remove_component::<DetectsMonsters>(*player_entity);
+                        gamelog.push("You no longer sense the monsters.".to_string());
                     }
+                }
 
-                    for vis in viewshed.visible_tiles.iter() {
-                        let idx = map.xy_idx(vis.x, vis.y);
-                        map.revealed_tiles[idx] = true;
-                        map.visible_tiles[idx] = true;
+                // 見えている範囲に隠れたものがあれば、毎ターン一定の確率で見つける
+                for tile in player_visible_tiles.iter() {
+                    let idx = map.xy_idx(tile.x, tile.y);
+                    for entity in map.tile_content[idx].iter() {
+                        if world.get_component::<Hidden>(*entity).is_some()
+                            && rng.roll_dice(1, 24) == 1
+                        {
+                            commands.remove_component::<Hidden>(*entity);
+                            gamelog.push(format!("You spotted a {}.", get_name(world, *entity)));
+                        }
                     }
                 }
-            }
-        })
+            },
+        )
 }